@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::config::AppConfig;
+use crate::debounce::PressAction;
+use crate::hotkey;
+use crate::profile::ConfigOverrides;
+
+/// 热键触发的动作
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    /// 粘贴剪贴板中的图片或文件路径
+    #[default]
+    Paste,
+    /// 粘贴图片历史队列中的所有路径
+    PasteHistory,
+    /// 将剪贴板中的 WSL 路径转换为 Windows 路径
+    Reverse,
+}
+
+impl ActionKind {
+    /// 结合单击 / 双击得出实际执行的动作：粘贴热键双击时粘贴历史
+    pub fn for_press(self, press: PressAction) -> ActionKind {
+        match (self, press) {
+            (ActionKind::Paste, PressAction::DoubleTap) => ActionKind::PasteHistory,
+            (kind, _) => kind,
+        }
+    }
+}
+
+/// 热键动作表中的一项（`[[action]]`）
+///
+/// ```toml
+/// [[action]]
+/// hotkey = "!+v"
+/// path_style = "windows"
+///
+/// [[action]]
+/// hotkey = "!b"
+/// paste_format = "attachment"
+///
+/// [[action]]
+/// hotkey = "!h"
+/// action = "paste_history"
+/// ```
+///
+/// 覆盖项在 `[[profile]]` 之后应用，只作用于该热键。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HotkeyAction {
+    /// 热键组合，语法与 `hotkey` 相同
+    pub hotkey: String,
+
+    #[serde(default)]
+    pub action: ActionKind,
+
+    #[serde(flatten)]
+    pub overrides: ConfigOverrides,
+}
+
+/// 按热键事件 id 分发的动作表
+///
+/// 包含主热键（粘贴）、反向转换热键与 `[[action]]`；同一热键出现多次时取第一项。
+#[derive(Debug, Clone, Default)]
+pub struct ActionTable {
+    entries: Vec<(u32, HotkeyAction)>,
+}
+
+impl ActionTable {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let builtin = std::iter::once(HotkeyAction {
+            hotkey: config.hotkey.clone(),
+            ..HotkeyAction::default()
+        })
+        .chain(config.reverse_hotkey.iter().map(|combo| HotkeyAction {
+            hotkey: combo.clone(),
+            action: ActionKind::Reverse,
+            ..HotkeyAction::default()
+        }));
+
+        let mut table = Self::default();
+        for action in builtin.chain(config.actions.iter().cloned()) {
+            let id = match hotkey::hotkey_id(&action.hotkey) {
+                Ok(id) => id,
+                Err(e) => {
+                    warn!("忽略无效的热键动作: {}", e);
+                    continue;
+                }
+            };
+
+            if table.get(id).is_some() {
+                warn!("热键 {} 已绑定其他动作，忽略重复项", action.hotkey);
+                continue;
+            }
+            table.entries.push((id, action));
+        }
+
+        table
+    }
+
+    pub fn get(&self, id: u32) -> Option<&HotkeyAction> {
+        self.entries
+            .iter()
+            .find(|(entry_id, _)| *entry_id == id)
+            .map(|(_, action)| action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ActionKind, ActionTable};
+    use crate::config::{AppConfig, PasteFormat, PathStyle};
+    use crate::debounce::PressAction;
+    use crate::hotkey::hotkey_id;
+
+    #[test]
+    fn dispatches_configured_hotkeys_by_id() {
+        let config: AppConfig = toml::from_str(
+            r#"
+            hotkey = "!v"
+            runtime_mode = "fast"
+            paste_format = "plain"
+            reverse_hotkey = "^!w"
+
+            [[action]]
+            hotkey = "Alt+Shift+V"
+            path_style = "windows"
+
+            [[action]]
+            hotkey = "!b"
+            paste_format = "attachment"
+
+            [[action]]
+            hotkey = "!h"
+            action = "paste_history"
+
+            [[action]]
+            hotkey = "Alt+V"
+            action = "reverse"
+
+            [[action]]
+            hotkey = "!F25"
+            "#,
+        )
+        .unwrap();
+
+        let table = ActionTable::from_config(&config);
+        let action = |combo: &str| table.get(hotkey_id(combo).unwrap()).unwrap();
+
+        assert_eq!(action("!v").action, ActionKind::Paste);
+        assert_eq!(action("^!w").action, ActionKind::Reverse);
+        assert_eq!(action("!+v").overrides.path_style, Some(PathStyle::Windows));
+        assert!(matches!(action("!b").overrides.paste_format, Some(PasteFormat::Attachment)));
+        assert_eq!(action("!h").action, ActionKind::PasteHistory);
+        assert!(table.get(hotkey_id("!x").unwrap()).is_none());
+    }
+
+    #[test]
+    fn double_tap_on_paste_pastes_history() {
+        assert_eq!(
+            ActionKind::Paste.for_press(PressAction::DoubleTap),
+            ActionKind::PasteHistory
+        );
+        assert_eq!(ActionKind::Paste.for_press(PressAction::Single), ActionKind::Paste);
+        assert_eq!(
+            ActionKind::Reverse.for_press(PressAction::DoubleTap),
+            ActionKind::Reverse
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use windows::core::PCWSTR;
+use windows::Win32::Storage::FileSystem::QueryDosDeviceW;
+
+use crate::path::PathCanonicalizer;
+
+/// 基于 Win32 的物理路径解析器
+///
+/// `std::fs::canonicalize` 底层调用 GetFinalPathNameByHandleW，会穿透目录联接、
+/// 符号链接与 subst 盘符；subst 目标由 QueryDosDeviceW 查询。
+pub struct Win32Canonicalizer;
+
+impl PathCanonicalizer for Win32Canonicalizer {
+    fn canonicalize(&self, path: &str) -> Option<String> {
+        std::fs::canonicalize(path)
+            .ok()
+            .map(|resolved| resolved.to_string_lossy().into_owned())
+    }
+
+    fn subst_target(&self, drive: char) -> Option<String> {
+        let device: Vec<u16> = format!("{}:", drive.to_ascii_uppercase())
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        let mut buffer = vec![0u16; 1024];
+
+        let len = unsafe { QueryDosDeviceW(PCWSTR::from_raw(device.as_ptr()), Some(&mut buffer)) };
+        if len == 0 {
+            return None;
+        }
+
+        // 返回多个以 NUL 分隔的字符串，第一项为当前映射
+        let end = buffer.iter().position(|&unit| unit == 0).unwrap_or(len as usize);
+        let target = String::from_utf16_lossy(&buffer[..end]);
+
+        // subst 盘符指向 \??\C:\dir，普通卷指向 \Device\HarddiskVolumeN
+        target.starts_with(r"\??\").then_some(target)
+    }
+}
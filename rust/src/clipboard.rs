@@ -1,22 +1,130 @@
 use std::io::Cursor;
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
 use windows::Win32::Foundation::{HANDLE, HGLOBAL};
 use windows::Win32::System::DataExchange::{
-    CloseClipboard, GetClipboardData, OpenClipboard,
+    CloseClipboard, EmptyClipboard, EnumClipboardFormats, GetClipboardData, GetClipboardOwner,
+    GetOpenClipboardWindow, OpenClipboard,
 };
 use windows::Win32::System::Memory::{GlobalLock, GlobalSize, GlobalUnlock};
-use windows::Win32::System::Ole::{CF_BITMAP, CF_DIB, CF_DIBV5, CF_HDROP};
+use windows::Win32::System::Ole::{CF_BITMAP, CF_DIB, CF_DIBV5, CF_HDROP, CF_UNICODETEXT};
 use windows::Win32::UI::Shell::{DragQueryFileW, HDROP};
 
+use anyhow::{bail, Result};
 use tracing::{info, warn};
 
+use crate::config::ClipboardRetryConfig;
+use crate::window;
+
 #[link(name = "user32")]
 extern "system" {
     fn IsClipboardFormatAvailable(format: u32) -> i32;
     fn GetClipboardSequenceNumber() -> u32;
 }
 
+/// 全局剪贴板重试策略，启动时由配置设置
+static RETRY_POLICY: RwLock<Option<ClipboardRetryConfig>> = RwLock::new(None);
+
+/// 设置打开剪贴板的重试策略
+pub fn set_retry_policy(policy: ClipboardRetryConfig) {
+    if let Ok(mut current) = RETRY_POLICY.write() {
+        *current = Some(policy);
+    }
+}
+
+fn retry_policy() -> ClipboardRetryConfig {
+    RETRY_POLICY
+        .read()
+        .ok()
+        .and_then(|policy| policy.clone())
+        .unwrap_or_default()
+}
+
+impl ClipboardRetryConfig {
+    /// 每次重试前的等待时间：从 initial_delay_ms 开始指数翻倍，不超过 max_delay_ms
+    pub fn delays(&self) -> impl Iterator<Item = Duration> {
+        let max_delay = self.max_delay_ms.max(self.initial_delay_ms);
+        std::iter::successors(Some(self.initial_delay_ms), move |delay| {
+            Some(delay.saturating_mul(2).min(max_delay))
+        })
+        .take(self.attempts.saturating_sub(1) as usize)
+        .map(Duration::from_millis)
+    }
+}
+
+/// 已打开的剪贴板，离开作用域时自动 CloseClipboard
+///
+/// 打开失败时按重试策略退避重试；最终失败时记录占用剪贴板的进程，
+/// 便于排查 Ditto、远程桌面剪贴板同步等长时间持有剪贴板的程序。
+pub struct ClipboardSession {
+    _private: (),
+}
+
+impl ClipboardSession {
+    pub fn open() -> Result<Self> {
+        let policy = retry_policy();
+        let mut delays = policy.delays();
+
+        loop {
+            if unsafe { OpenClipboard(None) }.is_ok() {
+                return Ok(Self { _private: () });
+            }
+
+            match delays.next() {
+                Some(delay) => std::thread::sleep(delay),
+                None => {
+                    let owner = clipboard_holder();
+                    warn!("剪贴板被占用，重试 {} 次后放弃: {}", policy.attempts, owner);
+                    bail!("无法打开剪贴板（被 {} 占用）", owner);
+                }
+            }
+        }
+    }
+
+    /// 读取指定格式的数据句柄
+    pub fn get_data(&self, format: u32) -> Option<HANDLE> {
+        unsafe { GetClipboardData(format).ok() }
+    }
+
+    /// 清空剪贴板并取得所有权
+    pub fn empty(&self) -> Result<()> {
+        unsafe { EmptyClipboard() }.map_err(|e| anyhow::anyhow!("清空剪贴板失败: {:?}", e))
+    }
+}
+
+impl Drop for ClipboardSession {
+    fn drop(&mut self) {
+        unsafe {
+            CloseClipboard().ok();
+        }
+    }
+}
+
+/// 描述当前打开（或拥有）剪贴板的进程
+fn clipboard_holder() -> String {
+    unsafe {
+        let open_window = GetOpenClipboardWindow();
+        let hwnd = if open_window.0 != 0 {
+            open_window
+        } else {
+            GetClipboardOwner()
+        };
+
+        if hwnd.0 == 0 {
+            return "未知进程".to_string();
+        }
+
+        let (process_id, process_name) = window::window_process(hwnd);
+        format!(
+            "{} (pid {}, hwnd {:#x})",
+            process_name.as_deref().unwrap_or("未知进程"),
+            process_id,
+            hwnd.0
+        )
+    }
+}
+
 /// 缓存的图片数据
 struct ImageCache {
     /// 剪贴板序列号
@@ -25,27 +133,26 @@ struct ImageCache {
     png_data: Vec<u8>,
     /// Windows 路径
     win_path: PathBuf,
-    /// WSL 路径
-    wsl_path: String,
+}
+
+/// 剪贴板快照：粘贴路径前保存的全部格式数据，粘贴完成后写回
+pub struct ClipboardSnapshot {
+    /// 快照时的剪贴板序列号
+    seq: u32,
+    /// (格式, 数据)
+    entries: Vec<(u32, Vec<u8>)>,
 }
 
 /// 剪贴板管理器
 pub struct ClipboardManager {
     temp_dir: PathBuf,
-    wsl_temp_dir: String,
     cache: Mutex<Option<ImageCache>>,
 }
 
 impl ClipboardManager {
     pub fn new(temp_dir: PathBuf) -> Self {
-        // 预计算 WSL 路径（匹配 AHK 的 gWslTempDir 优化）
-        let wsl_temp_dir = convert_path_to_wsl(&temp_dir.to_string_lossy());
-
-        info!("WSL 临时目录: {}", wsl_temp_dir);
-
         Self {
             temp_dir,
-            wsl_temp_dir,
             cache: Mutex::new(None),
         }
     }
@@ -63,29 +170,86 @@ impl ClipboardManager {
         unsafe { IsClipboardFormatAvailable(CF_HDROP.0 as u32) != 0 }
     }
 
-    pub fn read_file_list_for_paste(&self) -> Option<String> {
-        let paths = self.get_file_paths()?;
-        let wsl_paths: Vec<String> = paths
-            .iter()
-            .map(|path| convert_path_to_wsl(path))
-            .filter(|path| !path.is_empty())
-            .collect();
+    /// 读取资源管理器复制的文件列表（Windows 路径）
+    pub fn read_file_list_for_paste(&self) -> Option<Vec<String>> {
+        self.get_file_paths()
+    }
 
-        if wsl_paths.is_empty() {
-            None
-        } else {
-            Some(wsl_paths.join("\n"))
+    /// 读取剪贴板文本（CF_UNICODETEXT）
+    pub fn read_text(&self) -> Option<String> {
+        unsafe {
+            if IsClipboardFormatAvailable(CF_UNICODETEXT.0 as u32) == 0 {
+                return None;
+            }
+
+            let session = ClipboardSession::open().ok()?;
+            let h_data = session.get_data(CF_UNICODETEXT.0 as u32)?;
+            Self::read_unicode_text(h_data)
         }
     }
 
     /// 获取当前剪贴板序列号
-    fn get_sequence(&self) -> u32 {
+    pub fn get_sequence(&self) -> u32 {
         unsafe { GetClipboardSequenceNumber() }
     }
 
+    /// 保存剪贴板中所有基于全局内存的格式（CF_DIB、PNG、CF_HDROP、文本等）
+    ///
+    /// 位图、图元文件等 GDI 句柄格式无法按字节复制，跳过；
+    /// 系统会在恢复 CF_DIB 后重新合成 CF_BITMAP。
+    pub fn snapshot(&self) -> Option<ClipboardSnapshot> {
+        unsafe {
+            let Ok(session) = ClipboardSession::open() else {
+                warn!("无法打开剪贴板，跳过快照");
+                return None;
+            };
+
+            let seq = self.get_sequence();
+            let mut entries = Vec::new();
+            let mut format = EnumClipboardFormats(0);
+            while format != 0 {
+                if is_global_memory_format(format) {
+                    if let Some(h_data) = session.get_data(format) {
+                        if let Some(data) = Self::read_global_bytes(h_data) {
+                            entries.push((format, data));
+                        }
+                    }
+                }
+                format = EnumClipboardFormats(format);
+            }
+
+            drop(session);
+
+            info!("剪贴板快照: {} 种格式 (seq={})", entries.len(), seq);
+            Some(ClipboardSnapshot { seq, entries })
+        }
+    }
+
+    /// 写回快照，并把图片缓存迁移到恢复后的序列号，使再次粘贴仍命中缓存
+    pub fn restore(&self, snapshot: &ClipboardSnapshot) -> Result<()> {
+        let entries: Vec<(u32, &[u8])> = snapshot
+            .entries
+            .iter()
+            .map(|(format, data)| (*format, data.as_slice()))
+            .collect();
+        crate::paste::write_clipboard(&entries)?;
+
+        let restored_seq = self.get_sequence();
+        if let Ok(mut cache) = self.cache.lock() {
+            if let Some(ref mut cached) = *cache {
+                if cached.seq == snapshot.seq {
+                    cached.seq = restored_seq;
+                }
+            }
+        }
+
+        info!("已恢复剪贴板 (seq {} → {})", snapshot.seq, restored_seq);
+        Ok(())
+    }
+
     /// 读取图片并准备粘贴数据（含缓存）
-    /// 返回 (win_path, wsl_path, png_data)
-    pub fn read_image_for_paste(&self) -> Option<(PathBuf, String, Vec<u8>)> {
+    /// 返回 (win_path, png_data)
+    pub fn read_image_for_paste(&self) -> Option<(PathBuf, Vec<u8>)> {
         let seq = self.get_sequence();
 
         // 检查缓存
@@ -93,11 +257,7 @@ impl ClipboardManager {
             if let Some(ref cached) = *cache {
                 if cached.seq == seq && seq != 0 {
                     info!("使用缓存的图片数据 (seq={})", seq);
-                    return Some((
-                        cached.win_path.clone(),
-                        cached.wsl_path.clone(),
-                        cached.png_data.clone(),
-                    ));
+                    return Some((cached.win_path.clone(), cached.png_data.clone()));
                 }
             }
         }
@@ -111,11 +271,6 @@ impl ClipboardManager {
         let filename = format!("clip_{}.png", timestamp);
 
         let win_path = self.temp_dir.join(&filename);
-        let wsl_path = if !self.wsl_temp_dir.is_empty() {
-            format!("{}/{}", self.wsl_temp_dir, filename)
-        } else {
-            convert_path_to_wsl(&win_path.to_string_lossy())
-        };
 
         // 更新缓存
         if let Ok(mut cache) = self.cache.lock() {
@@ -123,26 +278,20 @@ impl ClipboardManager {
                 seq,
                 png_data: png_data.clone(),
                 win_path: win_path.clone(),
-                wsl_path: wsl_path.clone(),
             });
         }
 
-        Some((win_path, wsl_path, png_data))
+        Some((win_path, png_data))
     }
 
     fn get_file_paths(&self) -> Option<Vec<String>> {
         unsafe {
-            if OpenClipboard(None).is_err() {
-                return None;
-            }
-
-            let paths = if let Ok(h_data) = GetClipboardData(CF_HDROP.0 as u32) {
-                Self::read_hdrop_paths(HDROP(h_data.0 as isize))
-            } else {
-                Vec::new()
+            let session = ClipboardSession::open().ok()?;
+            let paths = match session.get_data(CF_HDROP.0 as u32) {
+                Some(h_data) => Self::read_hdrop_paths(HDROP(h_data.0 as isize)),
+                None => Vec::new(),
             };
-
-            CloseClipboard().ok();
+            drop(session);
 
             if paths.is_empty() {
                 None
@@ -155,21 +304,18 @@ impl ClipboardManager {
     /// 获取图片数据并转换为 PNG
     fn get_image_data(&self) -> Option<Vec<u8>> {
         unsafe {
-            if OpenClipboard(None).is_err() {
-                return None;
-            }
+            let session = ClipboardSession::open().ok()?;
 
             // 尝试获取 DIB 数据
-            let dib_data = if let Ok(h_data) = GetClipboardData(CF_DIB.0 as u32) {
+            let dib_data = if let Some(h_data) = session.get_data(CF_DIB.0 as u32) {
                 Some(Self::read_dib_data(h_data))
-            } else if let Ok(h_data) = GetClipboardData(CF_DIBV5.0 as u32) {
+            } else if let Some(h_data) = session.get_data(CF_DIBV5.0 as u32) {
                 Some(Self::read_dib_data(h_data))
             } else {
-                CloseClipboard().ok();
                 return None;
             };
 
-            CloseClipboard().ok();
+            drop(session);
 
             if let Some(dib) = dib_data {
                 if dib.is_empty() {
@@ -205,6 +351,37 @@ impl ClipboardManager {
         paths
     }
 
+    unsafe fn read_global_bytes(h_data: HANDLE) -> Option<Vec<u8>> {
+        let h_global = HGLOBAL(h_data.0 as *mut std::ffi::c_void);
+        let ptr = GlobalLock(h_global);
+        if ptr.is_null() {
+            return None;
+        }
+
+        let size = GlobalSize(h_global);
+        let data = std::slice::from_raw_parts(ptr as *const u8, size).to_vec();
+
+        let _ = GlobalUnlock(h_global);
+        Some(data)
+    }
+
+    unsafe fn read_unicode_text(h_data: HANDLE) -> Option<String> {
+        let h_global = HGLOBAL(h_data.0 as *mut std::ffi::c_void);
+        let ptr = GlobalLock(h_global);
+        if ptr.is_null() {
+            return None;
+        }
+
+        // 以分配大小为上限查找结尾 NUL，避免越界读取
+        let max_units = GlobalSize(h_global) / 2;
+        let units = std::slice::from_raw_parts(ptr as *const u16, max_units);
+        let len = units.iter().position(|&unit| unit == 0).unwrap_or(max_units);
+        let text = String::from_utf16_lossy(&units[..len]);
+
+        let _ = GlobalUnlock(h_global);
+        Some(text)
+    }
+
     /// 从剪贴板读取 DIB 数据
     unsafe fn read_dib_data(h_data: HANDLE) -> Vec<u8> {
         const MAX_DIB_SIZE: usize = 100 * 1024 * 1024;
@@ -361,27 +538,30 @@ impl ClipboardManager {
     }
 }
 
-/// 将 Windows 路径转换为 WSL 路径
-pub fn convert_path_to_wsl(path_str: &str) -> String {
-    let path_str = path_str.trim_matches('"');
-
-    // 处理驱动器路径 "C:\path\to\file"
-    if path_str.len() >= 3 && path_str.as_bytes()[1] == b':' && path_str.as_bytes()[2] == b'\\' {
-        let drive = &path_str[0..1];
-        let rest = path_str[3..].replace('\\', "/");
-        let rest = rest.trim_start_matches('/');
-        return format!("/mnt/{}/{}", drive.to_lowercase(), rest);
-    }
-
-    // 处理没有反斜杠的路径（如 D:\temp 变为 D:/temp）
-    if path_str.len() >= 3 && path_str.as_bytes()[1] == b':' {
-        let drive = &path_str[0..1];
-        let rest = path_str[2..].replace('\\', "/");
-        let rest = rest.trim_start_matches('/');
-        return format!("/mnt/{}/{}", drive.to_lowercase(), rest);
-    }
-
-    String::new()
+/// 判断剪贴板格式的数据是否为 GlobalAlloc 内存（可按字节保存与恢复）
+fn is_global_memory_format(format: u32) -> bool {
+    const CF_BITMAP: u32 = 2;
+    const CF_METAFILEPICT: u32 = 3;
+    const CF_PALETTE: u32 = 9;
+    const CF_ENHMETAFILE: u32 = 14;
+    const CF_OWNERDISPLAY: u32 = 0x80;
+    const CF_DSPBITMAP: u32 = 0x82;
+    const CF_DSPMETAFILEPICT: u32 = 0x83;
+    const CF_DSPENHMETAFILE: u32 = 0x8E;
+
+    !matches!(
+        format,
+        CF_BITMAP
+            | CF_METAFILEPICT
+            | CF_PALETTE
+            | CF_ENHMETAFILE
+            | CF_OWNERDISPLAY
+            | CF_DSPBITMAP
+            | CF_DSPMETAFILEPICT
+            | CF_DSPENHMETAFILE
+            // CF_PRIVATEFIRST..=CF_GDIOBJLAST：私有句柄与 GDI 对象
+            | 0x0200..=0x03FF
+    )
 }
 
 /// BITMAPINFOHEADER 结构（部分字段）
@@ -494,3 +674,32 @@ fn calculate_dib_copy_size(
     let image_size = row_size.checked_mul(height)?;
     pixel_offset.checked_add(image_size)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::config::ClipboardRetryConfig;
+    use std::time::Duration;
+
+    #[test]
+    fn retry_delays_back_off_exponentially_up_to_cap() {
+        let policy = ClipboardRetryConfig {
+            attempts: 6,
+            initial_delay_ms: 10,
+            max_delay_ms: 50,
+        };
+        let delays: Vec<Duration> = policy.delays().collect();
+        assert_eq!(
+            delays,
+            [10, 20, 40, 50, 50].map(Duration::from_millis).to_vec()
+        );
+
+        let single = ClipboardRetryConfig {
+            attempts: 1,
+            ..policy.clone()
+        };
+        assert_eq!(single.delays().count(), 0);
+
+        let none = ClipboardRetryConfig { attempts: 0, ..policy };
+        assert_eq!(none.delays().count(), 0);
+    }
+}
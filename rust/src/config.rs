@@ -1,18 +1,177 @@
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::action::HotkeyAction;
+use crate::config_edit;
+use crate::hotkey;
+use crate::paste::PasteKeystrokeSpec;
+use crate::path::PathMapping;
+use crate::profile::Profile;
+use crate::resolver::ResolverKind;
+use crate::target::TargetFilter;
 
 /// 应用配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
-    /// 热键组合: "!v", "^!v", "!Enter"
+    /// 热键组合，AutoHotkey 前缀或组合写法: "!v", "#^k", "Ctrl+Shift+Win+K", "!Numpad0"
     pub hotkey: String,
 
+    /// 热键被其他程序占用或无效时，按顺序尝试的后备热键（默认值避开 `[[action]]` 示例中的组合）
+    #[serde(default = "default_hotkey_fallbacks")]
+    pub hotkey_fallbacks: Vec<String>,
+
+    /// 托盘菜单中可切换的热键
+    #[serde(default = "default_hotkey_presets")]
+    pub hotkey_presets: Vec<String>,
+
     /// 运行模式: "safe" (输入法保护), "fast" (极速)
     pub runtime_mode: RuntimeMode,
 
-    /// 粘贴格式: "plain" (路径), "attachment" (附件)
+    /// 粘贴格式: "plain" (路径), "attachment" (附件), "data_uri" (data URI), "base64" (Base64 内容)
     pub paste_format: PasteFormat,
+
+    /// data_uri / base64 格式的 PNG 大小上限（字节），超过时改为粘贴路径
+    #[serde(default = "default_inline_max_bytes")]
+    pub inline_max_bytes: u64,
+
+    /// 热键去抖窗口（毫秒），窗口内的重复按键（按住、自动重复）合并为一次
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u64,
+
+    /// 双击热键的动作: "none", "paste_history" (粘贴历史队列中的所有图片)
+    #[serde(default)]
+    pub double_tap_action: DoubleTapAction,
+
+    /// 双击判定窗口（毫秒），应大于 debounce_ms；启用双击后单击会延迟到窗口结束才执行
+    #[serde(default = "default_double_tap_ms")]
+    pub double_tap_ms: u64,
+
+    /// 图片历史队列长度
+    #[serde(default = "default_history_size")]
+    pub history_size: usize,
+
+    /// 粘贴按键，与热键语法相同: "^v", "Ctrl+Shift+V", "Shift+Insert"，或 "RightClick"（鼠标右键）
+    #[serde(default = "default_paste_keystroke")]
+    pub paste_keystroke: PasteKeystrokeSpec,
+
+    /// 粘贴方式: "clipboard" (写剪贴板 + Ctrl+V), "typing" (逐字符模拟键入，不经过剪贴板)
+    ///
+    /// "typing" 时内联图片格式（data_uri、base64）回退为粘贴路径，避免逐字键入大段编码。
+    #[serde(default)]
+    pub paste_method: PasteMethod,
+
+    /// typing 模式每次 SendInput 发送的字符数，1 表示逐字符发送
+    #[serde(default = "default_typing_chunk_size")]
+    pub typing_chunk_size: usize,
+
+    /// typing 模式每批字符之间的间隔（毫秒）
+    #[serde(default = "default_typing_chunk_delay_ms")]
+    pub typing_chunk_delay_ms: u64,
+
+    /// 粘贴路径后是否恢复原剪贴板内容（截图、文件列表等）
+    #[serde(default = "default_restore_clipboard")]
+    pub restore_clipboard: bool,
+
+    /// 发送 Ctrl+V 后等待多久再恢复剪贴板（毫秒），需留给目标程序读取剪贴板
+    #[serde(default = "default_clipboard_restore_delay_ms")]
+    pub clipboard_restore_delay_ms: u64,
+
+    /// 路径格式: "wsl", "windows", "msys", "cygwin", "file_uri"
+    #[serde(default)]
+    pub path_style: PathStyle,
+
+    /// 路径解析: "logical" (保持原样), "physical" (展开 subst 盘符、目录联接与符号链接)
+    #[serde(default)]
+    pub path_resolution: PathResolution,
+
+    /// 项目根目录（Windows 或 WSL 路径），用于输出相对路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_root: Option<String>,
+
+    /// 是否输出相对于项目根目录的路径，根目录之外仍输出绝对路径
+    #[serde(default)]
+    pub relative_paths: bool,
+
+    /// 图片保存位置: "temp" (临时目录), "project" (项目仓库内的附件目录)
+    #[serde(default)]
+    pub save_location: SaveLocation,
+
+    /// 项目附件目录（相对项目根目录），会自动加入 `.git/info/exclude`
+    #[serde(default = "default_attachment_dir")]
+    pub attachment_dir: String,
+
+    /// 路径引用方式: "posix", "fish", "powershell", "none"
+    #[serde(default)]
+    pub quote_style: QuoteStyle,
+
+    /// 多个路径的分隔符: "space"（默认）, "newline", "nul"
+    ///
+    /// 旧版本固定以换行拼接，粘贴到 shell 时换行会直接执行命令，因此默认改为空格。
+    /// "nul" 是 NUL 安全的写法：NUL 本身无法经剪贴板或键入送达，改为空格分隔并给每个路径加引号
+    /// （`quote_style = "none"` 时按 POSIX 引用），含空格、换行等任意字符的路径都能被 shell 正确拆分。
+    #[serde(default)]
+    pub path_separator: PathSeparator,
+
+    /// 图片粘贴文本模板，如 "@{path} "、"![image]({wsl_path})"，占位符见 `template::TemplateVars`
+    #[serde(default = "default_output_template")]
+    pub image_template: String,
+
+    /// 文件列表中每个文件的粘贴文本模板，渲染结果按 `path_separator` 拼接
+    #[serde(default = "default_output_template")]
+    pub file_template: String,
+
+    /// 反向转换热键（WSL 路径 → Windows 路径），未配置时不注册
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverse_hotkey: Option<String>,
+
+    /// 反向转换输出: "text" (粘贴 Windows 路径), "files" (以文件形式放入剪贴板)
+    #[serde(default)]
+    pub reverse_output: ReverseOutput,
+
+    /// 反向转换输出文本的引用方式，目标为 Windows 程序，默认不加引号
+    #[serde(default = "default_reverse_quote_style")]
+    pub reverse_quote_style: QuoteStyle,
+
+    /// Linux 原生路径使用的发行版名称（`\\wsl.localhost\<distro>`），默认取 WSL 默认发行版
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wsl_distro: Option<String>,
+
+    /// WSL 路径解析链，按顺序尝试: "mapping", "command", "builtin"
+    #[serde(default = "default_path_resolvers")]
+    pub path_resolvers: Vec<ResolverKind>,
+
+    /// command 解析器的命令模板，`{path}` 替换为 Windows 路径，如 "wsl.exe -e wslpath -u {path}"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolver_command: Option<String>,
+
+    /// command 解析器超时（毫秒）
+    #[serde(default = "default_resolver_timeout_ms")]
+    pub resolver_timeout_ms: u64,
+
+    /// 打开剪贴板的重试策略（`[clipboard_retry]`）
+    #[serde(default)]
+    pub clipboard_retry: ClipboardRetryConfig,
+
+    /// 输入法保护设置（`[ime]`，仅安全模式生效）
+    #[serde(default)]
+    pub ime: ImeConfig,
+
+    /// 路径前缀映射规则（`[[path_mapping]]`），按最长前缀优先匹配
+    #[serde(default, rename = "path_mapping", skip_serializing_if = "Vec::is_empty")]
+    pub path_mappings: Vec<PathMapping>,
+
+    /// 按前台窗口覆盖配置（`[[profile]]`），按顺序取第一个匹配项
+    #[serde(default, rename = "profile", skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<Profile>,
+
+    /// 额外的热键动作（`[[action]]`），如 Alt+Shift+V 粘贴 Windows 路径、Alt+B 以附件粘贴
+    #[serde(default, rename = "action", skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<HotkeyAction>,
+
+    /// 热键生效的窗口范围（`[target]`），范围外的窗口中热键按原样转发
+    #[serde(default, skip_serializing_if = "TargetFilter::is_empty")]
+    pub target: TargetFilter,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,14 +186,321 @@ pub enum RuntimeMode {
 pub enum PasteFormat {
     Plain,
     Attachment,
+    /// `data:image/png;base64,...`
+    #[serde(rename = "data_uri")]
+    DataUri,
+    /// 仅 Base64 编码内容
+    Base64,
+}
+
+/// 剪贴板被其他程序占用时的重试策略
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardRetryConfig {
+    /// 最多尝试打开的次数（含第一次）
+    #[serde(default = "default_clipboard_retry_attempts")]
+    pub attempts: u32,
+
+    /// 第一次重试前的等待时间（毫秒），之后每次翻倍
+    #[serde(default = "default_clipboard_retry_initial_delay_ms")]
+    pub initial_delay_ms: u64,
+
+    /// 单次等待时间上限（毫秒）
+    #[serde(default = "default_clipboard_retry_max_delay_ms")]
+    pub max_delay_ms: u64,
+}
+
+impl Default for ClipboardRetryConfig {
+    fn default() -> Self {
+        Self {
+            attempts: default_clipboard_retry_attempts(),
+            initial_delay_ms: default_clipboard_retry_initial_delay_ms(),
+            max_delay_ms: default_clipboard_retry_max_delay_ms(),
+        }
+    }
+}
+
+fn default_clipboard_retry_attempts() -> u32 {
+    8
+}
+
+fn default_clipboard_retry_initial_delay_ms() -> u64 {
+    10
+}
+
+fn default_clipboard_retry_max_delay_ms() -> u64 {
+    200
+}
+
+/// 输入法保护设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImeConfig {
+    /// 切换方式: "layout" (切换键盘布局), "ime_status" (保持布局，关闭输入法的中文输入状态)
+    #[serde(default)]
+    pub mode: ImeSwitchMode,
+
+    /// layout 模式的目标布局 ID，如 "00000409" (美式)、"00010409" (Dvorak)、"00000809" (英国)
+    #[serde(default = "default_ime_layout")]
+    pub layout: String,
+
+    /// 发出切换请求后至少等待的时间（毫秒）
+    #[serde(default = "default_ime_switch_delay_ms")]
+    pub switch_delay_ms: u64,
+
+    /// 等待切换生效的最长时间（毫秒），超时仍未生效则记录警告后继续粘贴
+    #[serde(default = "default_ime_verify_timeout_ms")]
+    pub verify_timeout_ms: u64,
+
+    /// 粘贴后延迟多久恢复输入法（毫秒）
+    #[serde(default = "default_ime_restore_delay_ms")]
+    pub restore_delay_ms: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImeSwitchMode {
+    /// 通过 WM_INPUTLANGCHANGEREQUEST 切换到英文键盘布局
+    #[default]
+    Layout,
+    /// 通过 WM_IME_CONTROL 关闭输入法并切到字母数字转换模式（微软拼音等同一布局内切换中英文的输入法）
+    ImeStatus,
+}
+
+impl Default for ImeConfig {
+    fn default() -> Self {
+        Self {
+            mode: ImeSwitchMode::default(),
+            layout: default_ime_layout(),
+            switch_delay_ms: default_ime_switch_delay_ms(),
+            verify_timeout_ms: default_ime_verify_timeout_ms(),
+            restore_delay_ms: default_ime_restore_delay_ms(),
+        }
+    }
+}
+
+fn default_ime_layout() -> String {
+    "00000409".to_string()
+}
+
+fn default_ime_switch_delay_ms() -> u64 {
+    60
+}
+
+fn default_ime_verify_timeout_ms() -> u64 {
+    500
+}
+
+fn default_ime_restore_delay_ms() -> u64 {
+    120
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DoubleTapAction {
+    /// 不启用双击，每次按键立即执行
+    #[default]
+    None,
+    /// 粘贴历史队列中的所有图片路径
+    PasteHistory,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PasteMethod {
+    /// 写入剪贴板后发送 Ctrl+V
+    #[default]
+    Clipboard,
+    /// 以 KEYEVENTF_UNICODE 按键逐字符键入（适用于拦截 Ctrl+V 或禁用剪贴板的窗口）
+    Typing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathStyle {
+    /// `/mnt/c/...`
+    #[default]
+    Wsl,
+    /// `C:\...`
+    Windows,
+    /// MSYS / Git Bash: `/c/...`
+    Msys,
+    /// `/cygdrive/c/...`
+    Cygwin,
+    /// `file:///C:/...`
+    FileUri,
+}
+
+impl PathStyle {
+    pub fn all() -> &'static [PathStyle] {
+        &[
+            PathStyle::Wsl,
+            PathStyle::Windows,
+            PathStyle::Msys,
+            PathStyle::Cygwin,
+            PathStyle::FileUri,
+        ]
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            PathStyle::Wsl => "WSL (/mnt/c/...)",
+            PathStyle::Windows => "Windows (C:\\...)",
+            PathStyle::Msys => "Git Bash / MSYS (/c/...)",
+            PathStyle::Cygwin => "Cygwin (/cygdrive/c/...)",
+            PathStyle::FileUri => "文件 URI (file:///C:/...)",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathResolution {
+    #[default]
+    Logical,
+    Physical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SaveLocation {
+    #[default]
+    Temp,
+    Project,
+}
+
+fn default_hotkey_fallbacks() -> Vec<String> {
+    vec!["^!v".to_string(), "^!+v".to_string()]
+}
+
+fn default_hotkey_presets() -> Vec<String> {
+    vec!["!v".to_string(), "^!v".to_string(), "!Enter".to_string()]
+}
+
+fn default_inline_max_bytes() -> u64 {
+    1024 * 1024
+}
+
+fn default_output_template() -> String {
+    "{path}".to_string()
+}
+
+fn default_debounce_ms() -> u64 {
+    150
+}
+
+fn default_double_tap_ms() -> u64 {
+    350
+}
+
+fn default_history_size() -> usize {
+    10
+}
+
+fn default_paste_keystroke() -> PasteKeystrokeSpec {
+    PasteKeystrokeSpec::new("^v")
+}
+
+fn default_typing_chunk_size() -> usize {
+    16
+}
+
+fn default_typing_chunk_delay_ms() -> u64 {
+    5
+}
+
+fn default_restore_clipboard() -> bool {
+    true
+}
+
+fn default_clipboard_restore_delay_ms() -> u64 {
+    300
+}
+
+fn default_path_resolvers() -> Vec<ResolverKind> {
+    vec![ResolverKind::Mapping, ResolverKind::Builtin]
+}
+
+fn default_resolver_timeout_ms() -> u64 {
+    2000
+}
+
+fn default_reverse_quote_style() -> QuoteStyle {
+    QuoteStyle::None
+}
+
+fn default_attachment_dir() -> String {
+    ".ai-attachments".to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReverseOutput {
+    #[default]
+    Text,
+    Files,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteStyle {
+    /// bash / zsh 单引号
+    #[default]
+    Posix,
+    Fish,
+    PowerShell,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathSeparator {
+    #[default]
+    Space,
+    Newline,
+    Nul,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             hotkey: "!v".to_string(),
+            hotkey_fallbacks: default_hotkey_fallbacks(),
+            hotkey_presets: default_hotkey_presets(),
             runtime_mode: RuntimeMode::Fast,
             paste_format: PasteFormat::Plain,
+            inline_max_bytes: default_inline_max_bytes(),
+            debounce_ms: default_debounce_ms(),
+            double_tap_action: DoubleTapAction::default(),
+            double_tap_ms: default_double_tap_ms(),
+            history_size: default_history_size(),
+            paste_keystroke: default_paste_keystroke(),
+            paste_method: PasteMethod::default(),
+            typing_chunk_size: default_typing_chunk_size(),
+            typing_chunk_delay_ms: default_typing_chunk_delay_ms(),
+            restore_clipboard: default_restore_clipboard(),
+            clipboard_restore_delay_ms: default_clipboard_restore_delay_ms(),
+            path_style: PathStyle::default(),
+            path_resolution: PathResolution::default(),
+            project_root: None,
+            relative_paths: false,
+            save_location: SaveLocation::default(),
+            attachment_dir: default_attachment_dir(),
+            quote_style: QuoteStyle::default(),
+            path_separator: PathSeparator::default(),
+            image_template: default_output_template(),
+            file_template: default_output_template(),
+            reverse_hotkey: None,
+            reverse_output: ReverseOutput::default(),
+            reverse_quote_style: default_reverse_quote_style(),
+            wsl_distro: None,
+            path_resolvers: default_path_resolvers(),
+            resolver_command: None,
+            resolver_timeout_ms: default_resolver_timeout_ms(),
+            clipboard_retry: ClipboardRetryConfig::default(),
+            ime: ImeConfig::default(),
+            path_mappings: Vec::new(),
+            profiles: Vec::new(),
+            actions: Vec::new(),
+            target: TargetFilter::default(),
         }
     }
 }
@@ -46,7 +512,7 @@ impl AppConfig {
         if !config_path.exists() {
             // 创建默认配置
             let default = Self::default();
-            default.save()?;
+            default.write_new(&config_path)?;
             return Ok(default);
         }
 
@@ -59,18 +525,70 @@ impl AppConfig {
         Ok(config)
     }
 
-    pub fn save(&self) -> anyhow::Result<()> {
+    /// 保存配置：在配置文件上只改写相对 `previous` 变化的键，保留注释、未知键与其他手动修改
+    ///
+    /// 不要求文件能完整解析为配置（如手动填写了无法识别的值）；
+    /// 文件不是有效的 TOML 时不写入，返回错误。
+    pub fn save(&self, previous: &AppConfig) -> anyhow::Result<()> {
         let path = Self::config_path()?;
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => config_edit::update_document(&content, previous, self)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return self.write_new(&path),
+            Err(e) => return Err(e).context("读取配置文件失败"),
+        };
+
+        std::fs::write(&path, content)
+            .context("写入配置文件失败")?;
+
+        Ok(())
+    }
+
+    /// 写入完整配置（创建配置文件时使用）
+    fn write_new(&self, path: &Path) -> anyhow::Result<()> {
         let content = toml::to_string_pretty(self)
             .context("序列化配置失败")?;
 
-        std::fs::write(&path, content)
+        std::fs::write(path, content)
             .context("写入配置文件失败")?;
 
         Ok(())
     }
 
-    fn config_path() -> anyhow::Result<PathBuf> {
+    /// 校验需要解析的配置值（热键、粘贴按键、窗口标题正则），热重载时通过校验才应用
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let hotkeys = std::iter::once(&self.hotkey)
+            .chain(&self.hotkey_fallbacks)
+            .chain(&self.hotkey_presets)
+            .chain(&self.reverse_hotkey)
+            .chain(self.actions.iter().map(|action| &action.hotkey));
+        for combo in hotkeys {
+            hotkey::parse_hotkey(combo)?;
+        }
+
+        let keystrokes = std::iter::once(&self.paste_keystroke)
+            .chain(self.profiles.iter().filter_map(|profile| profile.overrides.paste_keystroke.as_ref()))
+            .chain(self.actions.iter().filter_map(|action| action.overrides.paste_keystroke.as_ref()));
+        for keystroke in keystrokes {
+            keystroke.keystroke()?;
+        }
+
+        let rules = self
+            .profiles
+            .iter()
+            .map(|profile| &profile.rule)
+            .chain(&self.target.include)
+            .chain(&self.target.exclude)
+            .chain(&self.target.fallback_include)
+            .chain(&self.target.fallback_exclude);
+        for rule in rules {
+            rule.validate()?;
+        }
+
+        Ok(())
+    }
+
+    /// 配置文件路径（可执行文件所在目录下的 wsl_clipboard.toml）
+    pub fn config_path() -> anyhow::Result<PathBuf> {
         let exe_dir = std::env::current_exe()
             .context("获取可执行文件路径失败")?
             .parent()
@@ -80,3 +598,31 @@ impl AppConfig {
         Ok(exe_dir.join("wsl_clipboard.toml"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::AppConfig;
+
+    #[test]
+    fn validate_reports_bad_values() {
+        let config = |extra: &str| -> AppConfig {
+            toml::from_str(&format!(
+                "hotkey = \"!v\"\nruntime_mode = \"safe\"\npaste_format = \"plain\"\n{extra}"
+            ))
+            .unwrap()
+        };
+
+        assert!(config("").validate().is_ok());
+        assert!(config("paste_keystroke = \"RightClick\"").validate().is_ok());
+
+        for bad in [
+            "hotkey_fallbacks = [\"^!F25\"]",
+            "paste_keystroke = \"Ctrl+\"",
+            "[[action]]\nhotkey = \"Hyper+V\"",
+            "[[profile]]\ntitle = \"(\"",
+            "[target]\nexclude = [{ title = \"[\" }]",
+        ] {
+            assert!(config(bad).validate().is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::Context;
+use toml_edit::{DocumentMut, Item, Table, TableLike};
+
+use crate::config::AppConfig;
+
+/// 在原配置文件文本上写入配置变更，保留注释、键顺序与未知键
+///
+/// 只修改 `old`（修改前的配置）与 `new` 序列化结果不同的键：值变化的键原位替换（保留行尾注释），
+/// 新增的键追加到所在表末尾（新增的表写在文件末尾），被移除的键从文件中删除。
+/// 文件只需是有效的 TOML，未改动的键即使无法识别也原样保留。
+pub fn update_document(content: &str, old: &AppConfig, new: &AppConfig) -> anyhow::Result<String> {
+    let mut document: DocumentMut = content
+        .parse()
+        .context("配置文件不是有效的 TOML，未保存修改")?;
+    let old = toml::Table::try_from(old).context("序列化配置失败")?;
+    let new = toml::Table::try_from(new).context("序列化配置失败")?;
+
+    let mut next_position = last_position(document.as_table()) + 1;
+    update_table(document.as_table_mut(), &old, &new, &mut next_position)?;
+    Ok(document.to_string())
+}
+
+fn update_table(
+    target: &mut dyn TableLike,
+    old: &toml::Table,
+    new: &toml::Table,
+    next_position: &mut usize,
+) -> anyhow::Result<()> {
+    for (key, new_value) in new {
+        let old_value = old.get(key);
+        if old_value == Some(new_value) {
+            continue;
+        }
+
+        // 两侧都是表时逐键比较，保留表内未改动的内容
+        if let (Some(toml::Value::Table(old_table)), toml::Value::Table(new_table)) = (old_value, new_value) {
+            if let Some(target_table) = target.get_mut(key).and_then(Item::as_table_like_mut) {
+                update_table(target_table, old_table, new_table, next_position)?;
+                continue;
+            }
+        }
+
+        let mut item = to_item(key, new_value)?;
+        place_tables(&mut item, next_position);
+        match target.get_mut(key) {
+            Some(existing) => {
+                if let (Item::Value(existing), Item::Value(value)) = (&*existing, &mut item) {
+                    *value.decor_mut() = existing.decor().clone();
+                }
+                *existing = item;
+            }
+            None => {
+                target.insert(key, item);
+            }
+        }
+    }
+
+    for key in old.keys() {
+        if !new.contains_key(key) {
+            target.remove(key);
+        }
+    }
+
+    Ok(())
+}
+
+/// 按 `toml::to_string_pretty` 的格式生成单个键的值（表写成 `[table]`，表数组写成 `[[array]]`）
+fn to_item(key: &str, value: &toml::Value) -> anyhow::Result<Item> {
+    let mut wrapper = toml::Table::new();
+    wrapper.insert(key.to_string(), value.clone());
+
+    let content = toml::to_string_pretty(&wrapper).context("序列化配置失败")?;
+    let mut document: DocumentMut = content.parse().context("序列化配置失败")?;
+    document
+        .remove(key)
+        .with_context(|| format!("序列化配置项 {} 失败", key))
+}
+
+/// 文件中最后一个表的位置
+fn last_position(table: &Table) -> usize {
+    let nested = table.iter().map(|(_, item)| match item {
+        Item::Table(table) => last_position(table),
+        Item::ArrayOfTables(array) => array.iter().map(last_position).max().unwrap_or(0),
+        _ => 0,
+    });
+    nested.chain(table.position()).max().unwrap_or(0)
+}
+
+/// 新生成的表带有其临时文档中的位置，重新编号使其写在文件末尾
+fn place_tables(item: &mut Item, next_position: &mut usize) {
+    let place = |table: &mut Table, next_position: &mut usize| {
+        table.set_position(*next_position);
+        *next_position += 1;
+        for (_, child) in table.iter_mut() {
+            place_tables(child, next_position);
+        }
+    };
+
+    match item {
+        Item::Table(table) => place(table, next_position),
+        Item::ArrayOfTables(array) => {
+            for table in array.iter_mut() {
+                place(table, next_position);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::update_document;
+    use crate::config::AppConfig;
+
+    const COMMENTED: &str = r#"# WSL 剪贴板配置
+hotkey = "!v"  # 主热键
+runtime_mode = "safe"
+paste_format = "plain"
+future_option = 42
+
+# 粘贴模板
+image_template = "@{path} "
+
+[clipboard_retry]
+# 重试次数
+attempts = 5
+
+[[profile]]
+name = "Terminal"
+process = "WindowsTerminal.exe"
+paste_keystroke = "^+v"
+"#;
+
+    fn apply(content: &str, edit: impl FnOnce(&mut AppConfig)) -> String {
+        let old: AppConfig = toml::from_str(content).unwrap();
+        let mut new = old.clone();
+        edit(&mut new);
+        update_document(content, &old, &new).unwrap()
+    }
+
+    #[test]
+    fn unchanged_config_round_trips_verbatim() {
+        assert_eq!(apply(COMMENTED, |_| {}), COMMENTED);
+    }
+
+    #[test]
+    fn only_touched_keys_change() {
+        let saved = apply(COMMENTED, |config| {
+            config.hotkey = "^!v".to_string();
+            config.clipboard_retry.attempts = 8;
+        });
+
+        let expected = COMMENTED
+            .replace("hotkey = \"!v\"  # 主热键", "hotkey = \"^!v\"  # 主热键")
+            .replace("attempts = 5", "attempts = 8");
+        assert_eq!(saved, expected);
+
+        let reloaded: AppConfig = toml::from_str(&saved).unwrap();
+        assert_eq!(reloaded.hotkey, "^!v");
+        assert_eq!(reloaded.clipboard_retry.attempts, 8);
+        assert_eq!(reloaded.profiles.len(), 1);
+    }
+
+    #[test]
+    fn added_and_removed_keys() {
+        let saved = apply(COMMENTED, |config| {
+            config.reverse_hotkey = Some("^!w".to_string());
+            config.profiles.clear();
+            config.ime.switch_delay_ms = 120;
+        });
+
+        assert!(saved.contains("# WSL 剪贴板配置"));
+        assert!(saved.contains("future_option = 42"));
+        assert!(saved.contains("# 重试次数"));
+        assert!(!saved.contains("[[profile]]"));
+        // 文件中没有的表追加到末尾
+        assert!(saved.find("[ime]").unwrap() > saved.find("attempts = 5").unwrap());
+
+        let reloaded: AppConfig = toml::from_str(&saved).unwrap();
+        assert_eq!(reloaded.reverse_hotkey.as_deref(), Some("^!w"));
+        assert!(reloaded.profiles.is_empty());
+        assert_eq!(reloaded.ime.switch_delay_ms, 120);
+
+        let restored = apply(&saved, |config| config.reverse_hotkey = None);
+        assert!(!restored.contains("reverse_hotkey"));
+        assert!(restored.contains("future_option = 42"));
+    }
+
+    #[test]
+    fn keeps_comments_when_file_has_unrecognized_values() {
+        let content = COMMENTED.replace("paste_format = \"plain\"", "paste_format = \"sticker\"  # 手动填写");
+        assert!(toml::from_str::<AppConfig>(&content).is_err());
+
+        let old: AppConfig = toml::from_str(COMMENTED).unwrap();
+        let mut new = old.clone();
+        new.hotkey = "^!v".to_string();
+
+        let saved = update_document(&content, &old, &new).unwrap();
+        let expected = content.replace("hotkey = \"!v\"  # 主热键", "hotkey = \"^!v\"  # 主热键");
+        assert_eq!(saved, expected);
+    }
+
+    #[test]
+    fn refuses_to_edit_invalid_toml() {
+        let old = AppConfig::default();
+        let new = AppConfig {
+            hotkey: "^!v".to_string(),
+            ..AppConfig::default()
+        };
+        assert!(update_document("# 未写完\nhotkey = \"!v", &old, &new).is_err());
+    }
+}
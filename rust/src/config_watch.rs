@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+
+/// 配置文件轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 文件变化后需保持不变的时长，编辑器保存时可能分多次写入
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+/// 配置文件的修改时间与大小
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some(FileStamp {
+        modified: metadata.modified().ok(),
+        len: metadata.len(),
+    })
+}
+
+/// 配置文件变化检测（去抖）
+///
+/// 文件戳变化后，需在 `settle` 时长内保持不变才触发重新加载；
+/// 文件暂时不存在（编辑器以重命名方式保存）时不触发。
+struct ChangeDetector {
+    settle: Duration,
+    current: Option<FileStamp>,
+    pending: Option<(FileStamp, Instant)>,
+}
+
+impl ChangeDetector {
+    fn new(current: Option<FileStamp>, settle: Duration) -> Self {
+        Self {
+            settle,
+            current,
+            pending: None,
+        }
+    }
+
+    /// 记录一次轮询结果，返回是否应重新加载
+    fn observe(&mut self, stamp: Option<FileStamp>, now: Instant) -> bool {
+        let Some(stamp) = stamp else {
+            self.pending = None;
+            return false;
+        };
+        if Some(stamp) == self.current {
+            self.pending = None;
+            return false;
+        }
+
+        match self.pending {
+            Some((pending, since)) if pending == stamp => {
+                if now.saturating_duration_since(since) < self.settle {
+                    return false;
+                }
+                self.current = Some(stamp);
+                self.pending = None;
+                true
+            }
+            _ => {
+                self.pending = Some((stamp, now));
+                false
+            }
+        }
+    }
+}
+
+/// 启动配置文件监视：文件变化稳定后重新读取并校验，结果发送到主循环
+///
+/// 读取或校验失败时发送错误，由主循环保留当前配置并提示。
+pub fn start_config_watcher() -> anyhow::Result<mpsc::Receiver<anyhow::Result<AppConfig>>> {
+    let path = AppConfig::config_path()?;
+    let (tx, rx) = mpsc::channel(4);
+
+    tokio::spawn(async move {
+        watch_config(path, tx).await;
+    });
+
+    Ok(rx)
+}
+
+async fn watch_config(path: PathBuf, tx: mpsc::Sender<anyhow::Result<AppConfig>>) {
+    let mut detector = ChangeDetector::new(file_stamp(&path), SETTLE_DELAY);
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    info!("监视配置文件: {}", path.display());
+
+    loop {
+        interval.tick().await;
+        if !detector.observe(file_stamp(&path), Instant::now()) {
+            continue;
+        }
+
+        info!("配置文件已修改，重新加载");
+        let reloaded = AppConfig::load().and_then(|config| {
+            config.validate()?;
+            Ok(config)
+        });
+        if let Err(e) = &reloaded {
+            warn!("配置文件校验失败: {:#}", e);
+        }
+
+        if tx.send(reloaded).await.is_err() {
+            break; // 主循环已退出
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChangeDetector, FileStamp};
+    use std::time::{Duration, Instant, SystemTime};
+
+    fn stamp(secs: u64, len: u64) -> Option<FileStamp> {
+        Some(FileStamp {
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)),
+            len,
+        })
+    }
+
+    #[test]
+    fn reloads_once_after_changes_settle() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut detector = ChangeDetector::new(stamp(1, 100), Duration::from_millis(300));
+
+        assert!(!detector.observe(stamp(1, 100), at(0)));
+
+        // 分两次写入：第二次写入重新计时
+        assert!(!detector.observe(stamp(2, 50), at(500)));
+        assert!(!detector.observe(stamp(2, 120), at(1000)));
+        assert!(!detector.observe(stamp(2, 120), at(1200)));
+        assert!(detector.observe(stamp(2, 120), at(1300)));
+
+        // 已加载的版本不再触发
+        assert!(!detector.observe(stamp(2, 120), at(2000)));
+    }
+
+    #[test]
+    fn missing_file_does_not_trigger() {
+        let start = Instant::now();
+        let mut detector = ChangeDetector::new(stamp(1, 100), Duration::ZERO);
+
+        assert!(!detector.observe(None, start));
+        assert!(!detector.observe(None, start + Duration::from_secs(1)));
+        // 以重命名方式保存后内容不变
+        assert!(!detector.observe(stamp(1, 100), start + Duration::from_secs(2)));
+    }
+}
@@ -0,0 +1,214 @@
+use std::time::{Duration, Instant};
+
+/// 时钟抽象，便于在测试中替换为可控时钟
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// 系统单调时钟
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// 一次（或两次）按键最终触发的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressAction {
+    Single,
+    DoubleTap,
+}
+
+/// 热键按压的去抖与合并
+///
+/// - 与上一次按键事件间隔小于 `debounce` 的事件视为按住或自动重复，直接合并；
+///   窗口随每个事件顺延，按住不放只触发一次。
+/// - 启用双击时，单击不会立即触发，而是等待 `double_tap` 窗口：
+///   窗口内再次按下触发 `DoubleTap`，超时后由 `expire` 触发 `Single`。
+pub struct PressTracker<C: Clock> {
+    clock: C,
+    debounce: Duration,
+    double_tap: Option<Duration>,
+    last_event: Option<Instant>,
+    pending_since: Option<Instant>,
+}
+
+impl<C: Clock> PressTracker<C> {
+    pub fn new(clock: C, debounce: Duration, double_tap: Option<Duration>) -> Self {
+        Self {
+            clock,
+            debounce,
+            double_tap,
+            last_event: None,
+            pending_since: None,
+        }
+    }
+
+    /// 记录一次按键（`at` 为热键事件产生的时间），返回需要立即执行的动作
+    ///
+    /// 上一次单击的双击窗口已过期但尚未 `expire` 时，返回该单击，本次按键进入等待。
+    pub fn press(&mut self, at: Instant) -> Option<PressAction> {
+        let coalesced = self
+            .last_event
+            .is_some_and(|last| at.saturating_duration_since(last) < self.debounce);
+        self.last_event = Some(at);
+        if coalesced {
+            return None;
+        }
+
+        let Some(window) = self.double_tap else {
+            return Some(PressAction::Single);
+        };
+
+        match self.pending_since.take() {
+            Some(first) if at.saturating_duration_since(first) <= window => {
+                Some(PressAction::DoubleTap)
+            }
+            // 窗口已过但 expire 尚未执行（主循环忙）：先触发等待中的单击，再等待新的一次
+            Some(_) => {
+                self.pending_since = Some(at);
+                Some(PressAction::Single)
+            }
+            None => {
+                self.pending_since = Some(at);
+                None
+            }
+        }
+    }
+
+    /// 等待中的单击应在何时触发
+    pub fn deadline(&self) -> Option<Instant> {
+        Some(self.pending_since? + self.double_tap?)
+    }
+
+    /// 双击窗口到期后触发等待中的单击
+    pub fn expire(&mut self) -> Option<PressAction> {
+        let deadline = self.deadline()?;
+        if self.clock.now() < deadline {
+            return None;
+        }
+
+        self.pending_since = None;
+        Some(PressAction::Single)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, PressAction, PressTracker};
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
+
+    /// 手动推进的测试时钟
+    #[derive(Clone)]
+    struct MockClock {
+        now: Rc<Cell<Instant>>,
+    }
+
+    impl MockClock {
+        fn new() -> Self {
+            Self {
+                now: Rc::new(Cell::new(Instant::now())),
+            }
+        }
+
+        fn advance(&self, ms: u64) -> Instant {
+            self.now.set(self.now.get() + Duration::from_millis(ms));
+            self.now.get()
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            self.now.get()
+        }
+    }
+
+    fn tracker(clock: &MockClock, double_tap_ms: Option<u64>) -> PressTracker<MockClock> {
+        PressTracker::new(
+            clock.clone(),
+            Duration::from_millis(150),
+            double_tap_ms.map(Duration::from_millis),
+        )
+    }
+
+    #[test]
+    fn held_key_and_auto_repeat_trigger_once() {
+        let clock = MockClock::new();
+        let mut presses = tracker(&clock, None);
+
+        assert_eq!(presses.press(clock.advance(0)), Some(PressAction::Single));
+        // 按住时每 30ms 一次自动重复，窗口持续顺延
+        for _ in 0..20 {
+            assert_eq!(presses.press(clock.advance(30)), None);
+        }
+        assert_eq!(presses.press(clock.advance(200)), Some(PressAction::Single));
+        assert_eq!(presses.expire(), None);
+    }
+
+    #[test]
+    fn double_tap_within_window() {
+        let clock = MockClock::new();
+        let mut presses = tracker(&clock, Some(400));
+
+        assert_eq!(presses.press(clock.advance(0)), None);
+        assert!(presses.deadline().is_some());
+        assert_eq!(presses.press(clock.advance(200)), Some(PressAction::DoubleTap));
+        assert_eq!(presses.deadline(), None);
+
+        clock.advance(1000);
+        assert_eq!(presses.expire(), None);
+    }
+
+    #[test]
+    fn single_tap_fires_after_window_expires() {
+        let clock = MockClock::new();
+        let mut presses = tracker(&clock, Some(400));
+
+        let first = clock.advance(0);
+        assert_eq!(presses.press(first), None);
+        assert_eq!(presses.deadline(), Some(first + Duration::from_millis(400)));
+
+        clock.advance(399);
+        assert_eq!(presses.expire(), None);
+        clock.advance(1);
+        assert_eq!(presses.expire(), Some(PressAction::Single));
+        assert_eq!(presses.expire(), None);
+
+        // 窗口之后的按键重新开始计时
+        assert_eq!(presses.press(clock.advance(500)), None);
+        assert_eq!(presses.press(clock.advance(200)), Some(PressAction::DoubleTap));
+    }
+
+    #[test]
+    fn stale_single_tap_fires_before_next_press() {
+        let clock = MockClock::new();
+        let mut presses = tracker(&clock, Some(400));
+
+        assert_eq!(presses.press(clock.advance(0)), None);
+        // 主循环忙，expire 未在窗口到期时执行
+        let second = clock.advance(450);
+        assert_eq!(presses.press(second), Some(PressAction::Single));
+        assert_eq!(presses.deadline(), Some(second + Duration::from_millis(400)));
+
+        clock.advance(400);
+        assert_eq!(presses.expire(), Some(PressAction::Single));
+    }
+
+    #[test]
+    fn bounces_inside_double_tap_window_are_coalesced() {
+        let clock = MockClock::new();
+        let mut presses = tracker(&clock, Some(400));
+
+        assert_eq!(presses.press(clock.advance(0)), None);
+        assert_eq!(presses.press(clock.advance(40)), None);
+        assert_eq!(presses.press(clock.advance(40)), None);
+        assert!(presses.deadline().is_some());
+
+        clock.advance(400);
+        assert_eq!(presses.expire(), Some(PressAction::Single));
+    }
+}
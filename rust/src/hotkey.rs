@@ -1,54 +1,45 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use global_hotkey::{
     hotkey::{Code, HotKey, Modifiers},
     GlobalHotKeyEvent, GlobalHotKeyManager,
 };
-use tracing::{info, warn};
-
-/// 可用的热键列表
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum HotkeyType {
-    AltV,
-    CtrlAltV,
-    AltEnter,
-}
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
 
-impl HotkeyType {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            HotkeyType::AltV => "!v",
-            HotkeyType::CtrlAltV => "^!v",
-            HotkeyType::AltEnter => "!Enter",
-        }
-    }
+use crate::paste;
 
-    pub fn display_name(&self) -> &'static str {
-        match self {
-            HotkeyType::AltV => "Alt+V",
-            HotkeyType::CtrlAltV => "Ctrl+Alt+V",
-            HotkeyType::AltEnter => "Alt+Enter",
-        }
-    }
+/// 转发热键后等待注入的按键被系统处理，再重新注册热键
+const PASSTHROUGH_REREGISTER_DELAY: Duration = Duration::from_millis(50);
 
-    pub fn from_config(s: &str) -> Option<Self> {
-        match s {
-            "!v" | "Alt+V" => Some(HotkeyType::AltV),
-            "^!v" | "Ctrl+Alt+V" => Some(HotkeyType::CtrlAltV),
-            "!Enter" | "Alt+Enter" => Some(HotkeyType::AltEnter),
-            _ => None,
-        }
-    }
-
-    pub fn all() -> &'static [HotkeyType] {
-        &[HotkeyType::AltV, HotkeyType::CtrlAltV, HotkeyType::AltEnter]
-    }
+/// 热键解析错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum HotkeyParseError {
+    #[error("热键为空")]
+    Empty,
+    #[error("热键 \"{combo}\" 只有修饰键，缺少按键")]
+    MissingKey { combo: String },
+    #[error("热键 \"{combo}\" 中的修饰键 \"{modifier}\" 无效，可用 Ctrl / Alt / Shift / Win 或前缀 ^ ! + #")]
+    UnknownModifier { combo: String, modifier: String },
+    #[error("热键 \"{combo}\" 中的修饰键 \"{modifier}\" 重复")]
+    DuplicateModifier { combo: String, modifier: String },
+    #[error("热键 \"{combo}\" 中的按键 \"{key}\" 无效，可用字母、数字、F1-F24、标点、Insert、方向键、Numpad0-9 等")]
+    UnknownKey { combo: String, key: String },
 }
 
+/// 热键已被其他程序（或本程序的其他动作）注册
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("热键 {0} 已被占用")]
+pub struct HotkeyConflict(pub String);
+
 /// 简化的热键管理器，必须在有 Win32 消息循环的线程上创建
 pub struct HotkeyManager {
     manager: GlobalHotKeyManager,
     current_hotkey: Option<HotKey>,
-    current_type: HotkeyType,
+    current_combo: String,
+    reverse_hotkey: Option<HotKey>,
+    action_hotkeys: Vec<HotKey>,
+    /// 注册时发现被占用的主热键候选
+    conflicts: Vec<HotKey>,
 }
 
 impl HotkeyManager {
@@ -56,30 +47,43 @@ impl HotkeyManager {
         Ok(Self {
             manager: GlobalHotKeyManager::new()?,
             current_hotkey: None,
-            current_type: HotkeyType::AltV,
+            current_combo: String::new(),
+            reverse_hotkey: None,
+            action_hotkeys: Vec::new(),
+            conflicts: Vec::new(),
         })
     }
 
-    /// 注册热键
-    pub fn register(&mut self, hotkey_type: HotkeyType) -> Result<()> {
-        if self.current_type == hotkey_type && self.current_hotkey.is_some() {
+    /// 注册热键，替换当前热键
+    pub fn register(&mut self, combo: &str) -> Result<()> {
+        let (mods, key) = parse_hotkey(combo)?;
+        let new_hotkey = HotKey::new(Some(mods), key);
+        if self.current_hotkey == Some(new_hotkey) {
+            self.current_combo = combo.to_string();
             return Ok(());
         }
 
         let previous_hotkey = self.current_hotkey.clone();
-        let (mods, key) = parse_hotkey(hotkey_type.as_str())?;
-        let new_hotkey = HotKey::new(Some(mods), key);
 
         // 先注册新热键，避免失败时丢失旧热键绑定
-        self.manager.register(new_hotkey)?;
+        if let Err(e) = self.manager.register(new_hotkey) {
+            if let global_hotkey::Error::AlreadyRegistered(_) = e {
+                if !self.conflicts.contains(&new_hotkey) {
+                    self.conflicts.push(new_hotkey);
+                }
+                return Err(HotkeyConflict(format_hotkey(mods, key)).into());
+            }
+            return Err(e.into());
+        }
+        self.conflicts.retain(|hotkey| *hotkey != new_hotkey);
 
         // 新热键注册成功后再卸载旧热键，失败时回滚新热键
         if let Some(old_hotkey) = previous_hotkey {
             if let Err(e) = self.manager.unregister(old_hotkey) {
                 if let Err(rollback_err) = self.manager.unregister(new_hotkey) {
                     warn!(
-                        "回滚热键失败: 新热键={:?}, 错误={:?}",
-                        hotkey_type,
+                        "回滚热键失败: 新热键={}, 错误={:?}",
+                        combo,
                         rollback_err
                     );
                 }
@@ -88,14 +92,139 @@ impl HotkeyManager {
         }
 
         self.current_hotkey = Some(new_hotkey);
-        self.current_type = hotkey_type;
+        self.current_combo = combo.to_string();
+
+        info!("已注册热键: {}", format_hotkey(mods, key));
+        Ok(())
+    }
+
+    /// 按顺序尝试注册主热键候选（配置的热键及后备列表），返回成功注册的组合
+    ///
+    /// 全部失败时保持原有状态（启动时即未绑定）并返回 None。
+    pub fn register_with_fallback(&mut self, candidates: &[&str]) -> Option<String> {
+        for combo in candidates {
+            match self.register(combo) {
+                Ok(()) => return Some(combo.to_string()),
+                Err(e) => warn!("注册热键 {} 失败: {}", combo, e),
+            }
+        }
+
+        error!("所有候选热键均注册失败: {:?}", candidates);
+        None
+    }
+
+    /// 热键是否在注册时被发现已被占用
+    pub fn is_conflicted(&self, combo: &str) -> bool {
+        parse_hotkey(combo)
+            .is_ok_and(|(mods, key)| self.conflicts.contains(&HotKey::new(Some(mods), key)))
+    }
+
+    /// 注册反向转换热键（WSL 路径 → Windows 路径）
+    pub fn register_reverse(&mut self, combo: &str) -> Result<()> {
+        let (mods, key) = parse_hotkey(combo)?;
+        let hotkey = HotKey::new(Some(mods), key);
+
+        if self.reverse_hotkey == Some(hotkey) {
+            return Ok(());
+        }
+
+        self.manager.register(hotkey)?;
+        if let Some(old_hotkey) = self.reverse_hotkey.replace(hotkey) {
+            if let Err(e) = self.manager.unregister(old_hotkey) {
+                warn!("注销旧的反向转换热键失败: {}", e);
+            }
+        }
 
-        info!("已注册热键: {}", hotkey_type.display_name());
+        info!("已注册反向转换热键: {}", combo);
         Ok(())
     }
 
+    /// 注销反向转换热键
+    pub fn unregister_reverse(&mut self) -> Result<()> {
+        if let Some(hotkey) = self.reverse_hotkey.take() {
+            self.manager.unregister(hotkey)?;
+            info!("已注销反向转换热键");
+        }
+        Ok(())
+    }
+
+    /// 注册动作表（`[[action]]`）中的热键，替换之前的动作热键
+    ///
+    /// 与主热键、反向转换热键或前面的动作重复的组合跳过（与 `ActionTable` 一致，先绑定者生效）；
+    /// 任意一个热键注册失败时，注销本次新注册的热键并保留原有绑定。
+    pub fn register_actions(&mut self, combos: &[&str]) -> Result<()> {
+        let reserved: Vec<HotKey> = self
+            .current_hotkey
+            .iter()
+            .chain(&self.reverse_hotkey)
+            .copied()
+            .collect();
+        let hotkeys = action_hotkeys(combos, &reserved)?;
+        let mut added = Vec::new();
+
+        for hotkey in &hotkeys {
+            if self.action_hotkeys.contains(hotkey) {
+                continue;
+            }
+            if let Err(e) = self.manager.register(*hotkey) {
+                for hotkey in added {
+                    if let Err(rollback_err) = self.manager.unregister(hotkey) {
+                        warn!("回滚动作热键失败: {:?}", rollback_err);
+                    }
+                }
+                let combo = format_hotkey(hotkey.mods, hotkey.key);
+                return Err(anyhow::Error::from(e).context(format!("注册动作热键 {} 失败", combo)));
+            }
+            added.push(*hotkey);
+        }
+
+        for old_hotkey in &self.action_hotkeys {
+            if !hotkeys.contains(old_hotkey) {
+                if let Err(e) = self.manager.unregister(*old_hotkey) {
+                    warn!("注销旧的动作热键失败: {}", e);
+                }
+            }
+        }
+
+        if !hotkeys.is_empty() || !self.action_hotkeys.is_empty() {
+            info!("已注册 {} 个动作热键", hotkeys.len());
+        }
+        self.action_hotkeys = hotkeys;
+        Ok(())
+    }
+
+    /// 将已注册的热键按原样发送给前台窗口
+    ///
+    /// 注入的按键同样会触发已注册的热键，因此先临时注销，注入并等待处理后再重新注册。
+    pub fn passthrough(&mut self, id: u32) -> Result<()> {
+        let hotkey = self
+            .current_hotkey
+            .iter()
+            .chain(&self.reverse_hotkey)
+            .chain(&self.action_hotkeys)
+            .find(|hotkey| hotkey.id() == id)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("热键 {} 未注册", id))?;
+
+        let keystroke = paste::PasteKeystroke::parse(&format_hotkey(hotkey.mods, hotkey.key))?;
+        self.manager.unregister(hotkey)?;
+        let sent = paste::send_paste_keystroke(&keystroke);
+        std::thread::sleep(PASSTHROUGH_REREGISTER_DELAY);
+        self.manager
+            .register(hotkey)
+            .context("转发后重新注册热键失败")?;
+
+        sent
+    }
+
     /// 注销当前热键
     pub fn unregister(&mut self) -> Result<()> {
+        for hotkey in self.action_hotkeys.drain(..) {
+            self.manager.unregister(hotkey)?;
+        }
+        if let Some(hotkey) = self.reverse_hotkey.take() {
+            self.manager.unregister(hotkey)?;
+        }
         if let Some(hotkey) = self.current_hotkey.take() {
             self.manager.unregister(hotkey)?;
             info!("已注销热键");
@@ -103,16 +232,36 @@ impl HotkeyManager {
         Ok(())
     }
 
-    /// 获取当前热键类型
-    pub fn current_type(&self) -> HotkeyType {
-        self.current_type
+    /// 当前热键的配置字符串（未注册时为空）
+    pub fn current_combo(&self) -> &str {
+        &self.current_combo
+    }
+
+    /// 当前已注册的热键
+    pub fn current_hotkey(&self) -> Option<HotKey> {
+        self.current_hotkey
     }
 }
 
+/// 需要单独注册的动作热键：跳过已由 `reserved`（主热键、反向转换热键）或前面的动作占用的组合
+fn action_hotkeys(combos: &[&str], reserved: &[HotKey]) -> Result<Vec<HotKey>> {
+    let mut hotkeys: Vec<HotKey> = Vec::with_capacity(combos.len());
+    for combo in combos {
+        let (mods, key) = parse_hotkey(combo)?;
+        let hotkey = HotKey::new(Some(mods), key);
+        if !reserved.contains(&hotkey) && !hotkeys.contains(&hotkey) {
+            hotkeys.push(hotkey);
+        }
+    }
+    Ok(hotkeys)
+}
+
 /// 启动热键桥接：在 spawn_blocking 中监听 GlobalHotKeyEvent，
 /// 通过 tokio mpsc 通道发送到主循环
-pub fn start_hotkey_bridge() -> tokio::sync::mpsc::Receiver<u32> {
-    let (tx, rx) = tokio::sync::mpsc::channel::<u32>(32);
+///
+/// 附带事件产生时间，主循环处理较慢时排队的事件仍能按真实间隔去抖。
+pub fn start_hotkey_bridge() -> tokio::sync::mpsc::Receiver<(u32, Instant)> {
+    let (tx, rx) = tokio::sync::mpsc::channel::<(u32, Instant)>(32);
 
     tokio::task::spawn_blocking(move || {
         let receiver = GlobalHotKeyEvent::receiver();
@@ -120,7 +269,7 @@ pub fn start_hotkey_bridge() -> tokio::sync::mpsc::Receiver<u32> {
             match receiver.recv() {
                 Ok(event) => {
                     if event.state == global_hotkey::HotKeyState::Pressed {
-                        if tx.blocking_send(event.id).is_err() {
+                        if tx.blocking_send((event.id, Instant::now())).is_err() {
                             break; // 接收端已关闭
                         }
                     }
@@ -135,46 +284,366 @@ pub fn start_hotkey_bridge() -> tokio::sync::mpsc::Receiver<u32> {
     rx
 }
 
+/// 计算热键组合对应的事件 id（与 GlobalHotKeyEvent::id 一致）
+pub fn hotkey_id(combo: &str) -> Result<u32> {
+    let (mods, key) = parse_hotkey(combo)?;
+    Ok(HotKey::new(Some(mods), key).id())
+}
+
 /// 解析热键组合字符串
-fn parse_hotkey(combo: &str) -> Result<(Modifiers, Code)> {
-    let combo_lower = combo.to_lowercase();
+///
+/// 支持 AutoHotkey 前缀写法（`^` Ctrl、`!` Alt、`+` Shift、`#` Win，如 `#^k`）
+/// 与组合写法（如 `Ctrl+Shift+Win+K`、`Shift+Insert`），两者可混用。
+/// 按键名不区分大小写，见 [`NAMED_KEYS`]。
+pub fn parse_hotkey(combo: &str) -> Result<(Modifiers, Code), HotkeyParseError> {
     let mut mods = Modifiers::empty();
-
-    if combo_lower.contains("ctrl") || combo_lower.contains("control") || combo.contains('^') {
-        mods |= Modifiers::CONTROL;
+    let mut rest = combo.trim();
+    if rest.is_empty() {
+        return Err(HotkeyParseError::Empty);
     }
-    if combo_lower.contains("alt") || combo.contains('!') {
-        mods |= Modifiers::ALT;
+
+    let mut add_modifier = |modifier: Modifiers, name: &str| {
+        if mods.contains(modifier) {
+            return Err(HotkeyParseError::DuplicateModifier {
+                combo: combo.to_string(),
+                modifier: name.to_string(),
+            });
+        }
+        mods |= modifier;
+        Ok(())
+    };
+
+    // AutoHotkey 前缀；单独的 "+" 视为按键本身
+    while rest.len() > 1 {
+        let modifier = match rest.as_bytes()[0] {
+            b'^' => Modifiers::CONTROL,
+            b'!' => Modifiers::ALT,
+            b'+' => Modifiers::SHIFT,
+            b'#' => Modifiers::SUPER,
+            _ => break,
+        };
+        add_modifier(modifier, &rest[..1])?;
+        rest = &rest[1..];
     }
-    if combo_lower.contains("shift") || combo.contains('+') {
-        mods |= Modifiers::SHIFT;
+
+    let mut parts: Vec<&str> = rest.split('+').map(str::trim).collect();
+    let key_str = parts.pop().unwrap_or_default();
+    if key_str.is_empty() {
+        return Err(HotkeyParseError::MissingKey {
+            combo: combo.to_string(),
+        });
     }
 
-    // 提取按键
-    let key_str = combo
-        .replace("Ctrl+", "")
-        .replace("Control+", "")
-        .replace("Alt+", "")
-        .replace("Shift+", "")
-        .replace('!', "")
-        .replace('^', "")
-        .replace('+', "")
-        .trim()
-        .to_lowercase();
+    for part in parts {
+        let modifier = match part.to_lowercase().as_str() {
+            "ctrl" | "control" => Modifiers::CONTROL,
+            "alt" => Modifiers::ALT,
+            "shift" => Modifiers::SHIFT,
+            "win" | "windows" | "super" | "meta" => Modifiers::SUPER,
+            _ => {
+                return Err(HotkeyParseError::UnknownModifier {
+                    combo: combo.to_string(),
+                    modifier: part.to_string(),
+                })
+            }
+        };
+        add_modifier(modifier, part)?;
+    }
 
-    let key = parse_key_code(&key_str)
-        .ok_or_else(|| anyhow::anyhow!("无效的键名: {}", key_str))?;
+    let key = parse_key_code(key_str).ok_or_else(|| HotkeyParseError::UnknownKey {
+        combo: combo.to_string(),
+        key: key_str.to_string(),
+    })?;
 
     Ok((mods, key))
 }
 
+/// 将热键格式化为显示名，如 `Ctrl+Alt+V`、`Win+Numpad0`
+pub fn format_hotkey(mods: Modifiers, key: Code) -> String {
+    let mut name = String::new();
+    for (modifier, label) in [
+        (Modifiers::CONTROL, "Ctrl+"),
+        (Modifiers::ALT, "Alt+"),
+        (Modifiers::SHIFT, "Shift+"),
+        (Modifiers::SUPER, "Win+"),
+    ] {
+        if mods.contains(modifier) {
+            name.push_str(label);
+        }
+    }
+    name.push_str(&key_name(key));
+    name
+}
+
+/// 配置字符串对应的显示名，无效时原样返回
+pub fn display_name(combo: &str) -> String {
+    match parse_hotkey(combo) {
+        Ok((mods, key)) => format_hotkey(mods, key),
+        Err(_) => combo.to_string(),
+    }
+}
+
+/// 具名按键：(显示名, 别名, 键码, 虚拟键码)
+///
+/// 字母、数字、F1-F24 与 Numpad0-9 不在表中，由 [`parse_key_code`] 直接解析。
+/// 别名包含 `global_hotkey::Code` 的名称，如 `BracketLeft`、`NumpadAdd`。
+const NAMED_KEYS: &[(&str, &[&str], Code, u16)] = &[
+    ("Enter", &["Return"], Code::Enter, 0x0D),
+    ("Tab", &[], Code::Tab, 0x09),
+    ("Space", &[], Code::Space, 0x20),
+    ("Backspace", &["BS"], Code::Backspace, 0x08),
+    ("Escape", &["Esc"], Code::Escape, 0x1B),
+    ("Insert", &["Ins"], Code::Insert, 0x2D),
+    ("Delete", &["Del"], Code::Delete, 0x2E),
+    ("Home", &[], Code::Home, 0x24),
+    ("End", &[], Code::End, 0x23),
+    ("PageUp", &["PgUp"], Code::PageUp, 0x21),
+    ("PageDown", &["PgDn"], Code::PageDown, 0x22),
+    ("Left", &["ArrowLeft"], Code::ArrowLeft, 0x25),
+    ("Up", &["ArrowUp"], Code::ArrowUp, 0x26),
+    ("Right", &["ArrowRight"], Code::ArrowRight, 0x27),
+    ("Down", &["ArrowDown"], Code::ArrowDown, 0x28),
+    ("PrintScreen", &["PrtSc"], Code::PrintScreen, 0x2C),
+    ("CapsLock", &[], Code::CapsLock, 0x14),
+    ("ScrollLock", &[], Code::ScrollLock, 0x91),
+    ("NumLock", &[], Code::NumLock, 0x90),
+    (";", &["Semicolon"], Code::Semicolon, 0xBA),
+    ("=", &["Equal"], Code::Equal, 0xBB),
+    (",", &["Comma"], Code::Comma, 0xBC),
+    ("-", &["Minus"], Code::Minus, 0xBD),
+    (".", &["Period"], Code::Period, 0xBE),
+    ("/", &["Slash"], Code::Slash, 0xBF),
+    ("`", &["Backquote"], Code::Backquote, 0xC0),
+    ("[", &["BracketLeft"], Code::BracketLeft, 0xDB),
+    ("\\", &["Backslash"], Code::Backslash, 0xDC),
+    ("]", &["BracketRight"], Code::BracketRight, 0xDD),
+    ("'", &["Quote"], Code::Quote, 0xDE),
+    ("NumpadAdd", &[], Code::NumpadAdd, 0x6B),
+    ("NumpadSub", &["NumpadSubtract"], Code::NumpadSubtract, 0x6D),
+    ("NumpadMult", &["NumpadMultiply"], Code::NumpadMultiply, 0x6A),
+    ("NumpadDiv", &["NumpadDivide"], Code::NumpadDivide, 0x6F),
+    ("NumpadDot", &["NumpadDecimal"], Code::NumpadDecimal, 0x6E),
+    ("NumpadEnter", &[], Code::NumpadEnter, 0x0D),
+    ("Volume_Up", &["AudioVolumeUp"], Code::AudioVolumeUp, 0xAF),
+    ("Volume_Down", &["AudioVolumeDown"], Code::AudioVolumeDown, 0xAE),
+    ("Volume_Mute", &["AudioVolumeMute"], Code::AudioVolumeMute, 0xAD),
+    ("Media_Play_Pause", &["MediaPlayPause"], Code::MediaPlayPause, 0xB3),
+    ("Media_Stop", &["MediaStop"], Code::MediaStop, 0xB2),
+    ("Media_Next", &["MediaTrackNext"], Code::MediaTrackNext, 0xB0),
+    ("Media_Prev", &["MediaTrackPrevious"], Code::MediaTrackPrevious, 0xB1),
+];
+
+/// 需要以 KEYEVENTF_EXTENDEDKEY 发送的按键，否则会被当作小键盘区的同名键（如 Shift+Numpad0）
+pub fn is_extended_key(code: Code) -> bool {
+    matches!(
+        code,
+        Code::Insert
+            | Code::Delete
+            | Code::Home
+            | Code::End
+            | Code::PageUp
+            | Code::PageDown
+            | Code::ArrowLeft
+            | Code::ArrowUp
+            | Code::ArrowRight
+            | Code::ArrowDown
+            | Code::PrintScreen
+            | Code::NumLock
+            | Code::NumpadDivide
+            | Code::NumpadEnter
+            | Code::AudioVolumeUp
+            | Code::AudioVolumeDown
+            | Code::AudioVolumeMute
+            | Code::MediaPlayPause
+            | Code::MediaStop
+            | Code::MediaTrackNext
+            | Code::MediaTrackPrevious
+    )
+}
+
 fn parse_key_code(s: &str) -> Option<Code> {
-    match s {
-        "v" => Some(Code::KeyV),
-        "enter" => Some(Code::Enter),
-        "c" => Some(Code::KeyC),
-        "a" => Some(Code::KeyA),
-        "x" => Some(Code::KeyX),
+    let named = NAMED_KEYS.iter().find(|(name, aliases, _, _)| {
+        name.eq_ignore_ascii_case(s) || aliases.iter().any(|alias| alias.eq_ignore_ascii_case(s))
+    });
+    if let Some((_, _, code, _)) = named {
+        return Some(*code);
+    }
+
+    let lower = s.to_ascii_lowercase();
+    let numbered = |prefix: &str, max: u8| {
+        lower
+            .strip_prefix(prefix)
+            .and_then(|n| n.parse::<u8>().ok())
+            .filter(|n| *n <= max)
+    };
+
+    if let Some(n) = numbered("numpad", 9) {
+        return format!("Numpad{}", n).parse().ok();
+    }
+    if let Some(n) = numbered("f", 24).filter(|n| *n >= 1) {
+        return format!("F{}", n).parse().ok();
+    }
+
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) if ch.is_ascii_alphabetic() => {
+            format!("Key{}", ch.to_ascii_uppercase()).parse().ok()
+        }
+        (Some(ch), None) if ch.is_ascii_digit() => format!("Digit{}", ch).parse().ok(),
         _ => None,
     }
 }
+
+fn key_name(code: Code) -> String {
+    if let Some((name, _, _, _)) = NAMED_KEYS.iter().find(|(_, _, c, _)| *c == code) {
+        return name.to_string();
+    }
+
+    let name = code.to_string();
+    match name.strip_prefix("Key").or_else(|| name.strip_prefix("Digit")) {
+        Some(rest) => rest.to_string(),
+        None => name,
+    }
+}
+
+/// 将按键转换为 Win32 虚拟键码
+pub fn code_to_vk(code: Code) -> Option<u16> {
+    if let Some((_, _, _, vk)) = NAMED_KEYS.iter().find(|(_, _, c, _)| *c == code) {
+        return Some(*vk);
+    }
+
+    let name = code.to_string();
+
+    if let Some(letter) = name.strip_prefix("Key") {
+        return letter.bytes().next().map(u16::from);
+    }
+    if let Some(digit) = name.strip_prefix("Digit") {
+        return digit.bytes().next().map(u16::from);
+    }
+    if let Some(number) = name.strip_prefix("Numpad").and_then(|n| n.parse::<u16>().ok()) {
+        // VK_NUMPAD0 = 0x60
+        return Some(0x60 + number);
+    }
+    if let Some(number) = name.strip_prefix('F').and_then(|n| n.parse::<u16>().ok()) {
+        // VK_F1 = 0x70
+        return Some(0x6F + number);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        action_hotkeys, code_to_vk, format_hotkey, is_extended_key, parse_hotkey,
+        HotkeyParseError, NAMED_KEYS,
+    };
+    use global_hotkey::hotkey::{Code, HotKey, Modifiers};
+
+    #[test]
+    fn parses_prefix_and_combination_syntax() {
+        let cases = [
+            ("!v", Modifiers::ALT, Code::KeyV),
+            ("^!v", Modifiers::CONTROL | Modifiers::ALT, Code::KeyV),
+            ("!Enter", Modifiers::ALT, Code::Enter),
+            ("^+v", Modifiers::CONTROL | Modifiers::SHIFT, Code::KeyV),
+            ("Ctrl+V", Modifiers::CONTROL, Code::KeyV),
+            ("Ctrl+Shift+V", Modifiers::CONTROL | Modifiers::SHIFT, Code::KeyV),
+            ("Shift+Insert", Modifiers::SHIFT, Code::Insert),
+            ("+Insert", Modifiers::SHIFT, Code::Insert),
+            ("#F12", Modifiers::SUPER, Code::F12),
+            (
+                "#^!+k",
+                Modifiers::SUPER | Modifiers::CONTROL | Modifiers::ALT | Modifiers::SHIFT,
+                Code::KeyK,
+            ),
+            ("Ctrl+Shift+Win+K", Modifiers::CONTROL | Modifiers::SHIFT | Modifiers::SUPER, Code::KeyK),
+            ("win + numpad0", Modifiers::SUPER, Code::Numpad0),
+            ("^NumpadAdd", Modifiers::CONTROL, Code::NumpadAdd),
+            ("Alt+;", Modifiers::ALT, Code::Semicolon),
+            ("!BracketLeft", Modifiers::ALT, Code::BracketLeft),
+            ("^!Left", Modifiers::CONTROL | Modifiers::ALT, Code::ArrowLeft),
+            ("F24", Modifiers::empty(), Code::F24),
+        ];
+
+        for (combo, mods, code) in cases {
+            assert_eq!(parse_hotkey(combo).unwrap(), (mods, code), "combo: {combo}");
+        }
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        let unknown_key = |combo: &str, key: &str| HotkeyParseError::UnknownKey {
+            combo: combo.to_string(),
+            key: key.to_string(),
+        };
+
+        assert_eq!(parse_hotkey("  "), Err(HotkeyParseError::Empty));
+        assert_eq!(parse_hotkey("^F25"), Err(unknown_key("^F25", "F25")));
+        assert_eq!(parse_hotkey("Alt+Numpad10"), Err(unknown_key("Alt+Numpad10", "Numpad10")));
+        assert_eq!(
+            parse_hotkey("Ctrl+Alt+"),
+            Err(HotkeyParseError::MissingKey { combo: "Ctrl+Alt+".to_string() })
+        );
+        assert_eq!(
+            parse_hotkey("Hyper+V"),
+            Err(HotkeyParseError::UnknownModifier {
+                combo: "Hyper+V".to_string(),
+                modifier: "Hyper".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_hotkey("^Ctrl+V"),
+            Err(HotkeyParseError::DuplicateModifier {
+                combo: "^Ctrl+V".to_string(),
+                modifier: "Ctrl".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn display_names_parse_back() {
+        assert_eq!(format_hotkey(Modifiers::CONTROL | Modifiers::ALT, Code::KeyV), "Ctrl+Alt+V");
+        assert_eq!(format_hotkey(Modifiers::SUPER, Code::Numpad0), "Win+Numpad0");
+
+        for (name, aliases, code, _) in NAMED_KEYS {
+            let combo = format_hotkey(Modifiers::SHIFT, *code);
+            assert_eq!(parse_hotkey(&combo).unwrap(), (Modifiers::SHIFT, *code), "key: {name}");
+            for alias in *aliases {
+                assert_eq!(parse_hotkey(alias).unwrap().1, *code, "alias: {alias}");
+            }
+        }
+    }
+
+    #[test]
+    fn maps_codes_to_virtual_keys() {
+        assert_eq!(code_to_vk(Code::KeyV), Some(0x56));
+        assert_eq!(code_to_vk(Code::Digit1), Some(0x31));
+        assert_eq!(code_to_vk(Code::F1), Some(0x70));
+        assert_eq!(code_to_vk(Code::Insert), Some(0x2D));
+        assert!(is_extended_key(Code::Insert));
+        assert!(is_extended_key(Code::NumpadEnter));
+        assert!(!is_extended_key(Code::Enter));
+        assert!(!is_extended_key(Code::Numpad0));
+        assert_eq!(code_to_vk(Code::Numpad7), Some(0x67));
+        assert_eq!(code_to_vk(Code::Slash), Some(0xBF));
+        assert_eq!(code_to_vk(Code::Fn), None);
+    }
+
+    #[test]
+    fn actions_skip_combos_bound_to_builtin_hotkeys() {
+        let primary = HotKey::new(Some(Modifiers::ALT), Code::KeyV);
+        let reverse = HotKey::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyW);
+
+        let combos = ["Alt+V", "!b", "^!w", "Alt+B", "!h"];
+        let hotkeys = action_hotkeys(&combos, &[primary, reverse]).unwrap();
+        assert_eq!(
+            hotkeys,
+            vec![
+                HotKey::new(Some(Modifiers::ALT), Code::KeyB),
+                HotKey::new(Some(Modifiers::ALT), Code::KeyH),
+            ]
+        );
+        assert!(action_hotkeys(&["!b", "Hyper+V"], &[]).is_err());
+    }
+}
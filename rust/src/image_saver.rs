@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::sync::mpsc;
 use tracing::{info, warn};
 
@@ -18,7 +18,8 @@ pub fn start_saver() -> mpsc::Sender<(PathBuf, Vec<u8>)> {
     tx
 }
 
-async fn save_image(path: &PathBuf, data: &[u8]) -> anyhow::Result<()> {
+/// 写入图片（附件模式在粘贴前等待写入完成，确保文件存在）
+pub async fn save_image(path: &Path, data: &[u8]) -> anyhow::Result<()> {
     use anyhow::Context;
 
     if let Some(parent) = path.parent() {
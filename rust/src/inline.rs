@@ -0,0 +1,170 @@
+use std::io::{self, Write};
+
+use crate::config::PasteFormat;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// 每批编码的输入字节数（3 的倍数），对应 4 KiB 输出
+const CHUNK_INPUT: usize = 3 * 1024;
+
+/// 流式 Base64 编码器：按块编码后写入下层 writer，不保留整份中间缓冲
+pub struct Base64Writer<W: Write> {
+    inner: W,
+    /// 不足 3 字节的剩余输入
+    pending: [u8; 3],
+    pending_len: usize,
+}
+
+impl<W: Write> Base64Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            pending: [0; 3],
+            pending_len: 0,
+        }
+    }
+
+    /// 写出剩余字节与 `=` 填充，返回下层 writer
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.pending_len > 0 {
+            let mut block = [0u8; 3];
+            block[..self.pending_len].copy_from_slice(&self.pending[..self.pending_len]);
+            let mut encoded = encode_block(block);
+            for slot in encoded.iter_mut().skip(self.pending_len + 1) {
+                *slot = b'=';
+            }
+            self.inner.write_all(&encoded)?;
+        }
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for Base64Writer<W> {
+    fn write(&mut self, mut buf: &[u8]) -> io::Result<usize> {
+        let written = buf.len();
+
+        // 先补齐上次剩余的不完整分组
+        if self.pending_len > 0 {
+            let take = (3 - self.pending_len).min(buf.len());
+            self.pending[self.pending_len..self.pending_len + take].copy_from_slice(&buf[..take]);
+            self.pending_len += take;
+            buf = &buf[take..];
+
+            if self.pending_len < 3 {
+                return Ok(written);
+            }
+            self.inner.write_all(&encode_block(self.pending))?;
+            self.pending_len = 0;
+        }
+
+        let mut output = [0u8; CHUNK_INPUT / 3 * 4];
+        let complete = buf.len() - buf.len() % 3;
+        for chunk in buf[..complete].chunks(CHUNK_INPUT) {
+            for (block, out) in chunk.chunks_exact(3).zip(output.chunks_exact_mut(4)) {
+                out.copy_from_slice(&encode_block([block[0], block[1], block[2]]));
+            }
+            self.inner.write_all(&output[..chunk.len() / 3 * 4])?;
+        }
+
+        let rest = &buf[complete..];
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.pending_len = rest.len();
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn encode_block(block: [u8; 3]) -> [u8; 4] {
+    let n = u32::from_be_bytes([0, block[0], block[1], block[2]]);
+    [
+        ALPHABET[(n >> 18) as usize & 0x3F],
+        ALPHABET[(n >> 12) as usize & 0x3F],
+        ALPHABET[(n >> 6) as usize & 0x3F],
+        ALPHABET[n as usize & 0x3F],
+    ]
+}
+
+/// Base64 编码后的长度（含填充）
+pub fn encoded_len(input_len: usize) -> usize {
+    input_len.div_ceil(3) * 4
+}
+
+/// 将 PNG 编码为内联文本：`data_uri` 输出 `data:image/png;base64,...`，`base64` 只输出编码内容
+///
+/// 输出缓冲按最终长度一次分配，编码过程不产生整份中间副本；其他格式返回 None。
+pub fn encode_png(png_data: &[u8], format: &PasteFormat) -> Option<String> {
+    let prefix: &[u8] = match format {
+        PasteFormat::DataUri => b"data:image/png;base64,",
+        PasteFormat::Base64 => b"",
+        PasteFormat::Plain | PasteFormat::Attachment => return None,
+    };
+
+    let mut output = Vec::with_capacity(prefix.len() + encoded_len(png_data.len()));
+    output.extend_from_slice(prefix);
+
+    let mut writer = Base64Writer::new(output);
+    writer.write_all(png_data).ok()?;
+    let output = writer.finish().ok()?;
+
+    String::from_utf8(output).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_png, encoded_len, Base64Writer};
+    use crate::config::PasteFormat;
+    use std::io::Write;
+
+    fn encode(parts: &[&[u8]]) -> String {
+        let mut writer = Base64Writer::new(Vec::new());
+        for part in parts {
+            writer.write_all(part).unwrap();
+        }
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn matches_rfc4648_vectors() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(encode(&[input.as_bytes()]), expected);
+            assert_eq!(encoded_len(input.len()), expected.len());
+        }
+    }
+
+    #[test]
+    fn split_writes_match_single_write() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i * 7 % 256) as u8).collect();
+        let whole = encode(&[&data]);
+
+        for split in [1, 2, 3, 4, 3071, 3072, 3073, 9999] {
+            let (head, tail) = data.split_at(split);
+            assert_eq!(encode(&[head, tail]), whole, "split at {split}");
+        }
+        assert_eq!(encode(&[&data[..1], &data[1..2], &data[2..]]), whole);
+    }
+
+    #[test]
+    fn formats_data_uri_and_raw_payload() {
+        let png = b"\x89PNG";
+        assert_eq!(
+            encode_png(png, &PasteFormat::DataUri).as_deref(),
+            Some("data:image/png;base64,iVBORw==")
+        );
+        assert_eq!(encode_png(png, &PasteFormat::Base64).as_deref(), Some("iVBORw=="));
+        assert_eq!(encode_png(png, &PasteFormat::Plain), None);
+    }
+}
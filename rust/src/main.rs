@@ -1,27 +1,50 @@
 #![windows_subsystem = "windows"]
 
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
 use tracing::{error, info, warn};
 
+mod action;
+mod canonical;
 mod clipboard;
 mod cleanup;
 mod config;
+mod config_edit;
+mod config_watch;
+mod debounce;
 mod hotkey;
 mod image_saver;
+mod inline;
 mod paste;
+mod path;
+mod profile;
+mod project;
+mod quote;
+mod resolver;
+mod target;
+mod template;
 mod tray;
+mod typing;
+mod window;
 
+use action::{ActionKind, ActionTable, HotkeyAction};
 use clipboard::ClipboardManager;
-use config::RuntimeMode;
+use config::{
+    AppConfig, DoubleTapAction, PasteFormat, PasteMethod, PathResolution, ReverseOutput, RuntimeMode, SaveLocation,
+};
+use debounce::{PressAction, PressTracker, SystemClock};
 use paste::HKL;
-use tray::TrayCommand;
+use profile::WindowInfo;
+use resolver::{ChainResolver, PathResolver};
+use template::TemplateVars;
+use tray::{TrayCommand, TrayRequest};
 
 /// 应用运行时状态（可被托盘命令修改）
 struct AppState {
-    runtime_mode: RuntimeMode,
+    config: AppConfig,
 }
 
 #[tokio::main]
@@ -33,7 +56,7 @@ async fn main() -> Result<()> {
     info!("WSL Clipboard Helper v2.0.0 (Rust) 启动中...");
 
     // 加载配置
-    let app_config = config::AppConfig::load().unwrap_or_default();
+    let mut app_config = config::AppConfig::load().unwrap_or_default();
     info!(
         "加载配置: 热键={}, 模式={:?}",
         app_config.hotkey, app_config.runtime_mode
@@ -49,22 +72,28 @@ async fn main() -> Result<()> {
     info!("临时目录: {}", temp_dir.display());
 
     // 预加载英文输入法
-    let english_hkl = paste::preload_english_layout();
+    let english_hkl = paste::preload_english_layout(&app_config.ime.layout);
     info!("英文输入法 HKL: {:#x}", english_hkl);
 
+    // 剪贴板重试策略
+    clipboard::set_retry_policy(app_config.clipboard_retry.clone());
+
     // 创建剪贴板管理器
     let clipboard_manager = ClipboardManager::new(temp_dir.clone());
 
+    // WSL 路径解析链（command 解析器的缓存在整个运行期间复用）
+    let path_resolver = build_path_resolver(&app_config);
+
     // 启动图片保存异步任务（不再需要 temp_dir 参数）
     let save_tx = image_saver::start_saver();
 
     // 运行时状态
     let state = Arc::new(Mutex::new(AppState {
-        runtime_mode: app_config.runtime_mode.clone(),
+        config: app_config.clone(),
     }));
 
     // 启动托盘（含热键管理器）
-    let std_tray_rx = tray::TrayController::start(app_config, temp_dir.clone())?;
+    let std_tray_rx = tray::TrayController::start(app_config.clone(), temp_dir.clone())?;
 
     // 将 std mpsc 桥接到 tokio mpsc，以便在 select! 中使用
     let (tray_tx_bridge, mut tray_rx) = mpsc::channel::<TrayCommand>(32);
@@ -83,6 +112,7 @@ async fn main() -> Result<()> {
 
     // 启动热键桥接
     let mut hotkey_rx = hotkey::start_hotkey_bridge();
+    let mut actions = ActionTable::from_config(&app_config);
 
     // 定时清理任务
     let temp_dir_for_cleanup = temp_dir.clone();
@@ -98,32 +128,123 @@ async fn main() -> Result<()> {
 
     info!("WSL Clipboard Helper 已启动");
 
+    // 监视配置文件，修改后热重载
+    let mut config_rx = match config_watch::start_config_watcher() {
+        Ok(rx) => Some(rx),
+        Err(e) => {
+            warn!("无法监视配置文件，热重载不可用: {}", e);
+            None
+        }
+    };
+
+    let mut ctx = ActionContext {
+        clipboard_manager: &clipboard_manager,
+        save_tx: &save_tx,
+        resolver: path_resolver,
+        english_hkl,
+    };
+
+    // 每个热键各自去抖与双击判定
+    let mut presses: HashMap<u32, PressTracker<SystemClock>> = HashMap::new();
+    let mut image_history: Vec<String> = Vec::new();
+
     // 主事件循环
     loop {
+        let next_deadline = presses.values().filter_map(|tracker| tracker.deadline()).min();
+        let pending_deadline = next_deadline
+            .map(tokio::time::Instant::from_std)
+            .unwrap_or_else(tokio::time::Instant::now);
+
         tokio::select! {
-            // 热键触发
-            Some(_hotkey_id) = hotkey_rx.recv() => {
-                let mode = {
-                    let s = state.lock().await;
-                    s.runtime_mode.clone()
+            // 热键触发：按 id 查找动作，去抖、合并后按单击 / 双击执行
+            Some((hotkey_id, pressed_at)) = hotkey_rx.recv() => {
+                let Some(action) = actions.get(hotkey_id) else {
+                    continue;
                 };
-                match handle_paste(&clipboard_manager, &save_tx, &mode, english_hkl).await {
-                    Ok(_) => {}
+
+                // 前台窗口不在 [target] 范围内：热键原样交给该窗口
+                let window = window::foreground_window_info();
+                if !state.lock().await.config.target.allows(window.as_ref()) {
+                    info!("前台窗口不在热键生效范围内，转发热键 {}", action.hotkey);
+                    tray::request(TrayRequest::PassthroughHotkey(hotkey_id));
+                    continue;
+                }
+
+                let tracker = presses
+                    .entry(hotkey_id)
+                    .or_insert_with(|| press_tracker(&app_config, action.action));
+                if let Some(press) = tracker.press(pressed_at) {
+                    let trigger = Trigger { hotkey_id, window };
+                    run_action(&ctx, &state, action, press, &trigger, &mut image_history).await;
+                }
+            }
+            // 双击窗口到期，执行等待中的单击
+            _ = tokio::time::sleep_until(pending_deadline), if next_deadline.is_some() => {
+                let expired: Vec<(u32, PressAction)> = presses
+                    .iter_mut()
+                    .filter_map(|(id, tracker)| Some((*id, tracker.expire()?)))
+                    .collect();
+                for (hotkey_id, press) in expired {
+                    let Some(action) = actions.get(hotkey_id) else {
+                        continue;
+                    };
+                    let trigger = Trigger {
+                        hotkey_id,
+                        window: window::foreground_window_info(),
+                    };
+                    run_action(&ctx, &state, action, press, &trigger, &mut image_history).await;
+                }
+            }
+            // 配置文件热重载：校验通过后应用到热键、模式、粘贴格式与路径解析
+            Some(reloaded) = recv_reload(&mut config_rx) => {
+                let config = match reloaded {
+                    Ok(config) => config,
                     Err(e) => {
-                        error!("粘贴处理失败: {}", e);
+                        error!("配置文件有误，保留当前配置: {:#}", e);
+                        tray::request(TrayRequest::Notify {
+                            title: "配置文件有误".to_string(),
+                            text: format!("已保留当前配置: {:#}", e),
+                        });
+                        continue;
                     }
+                };
+
+                let mut s = state.lock().await;
+                if same_config(&s.config, &config) {
+                    continue; // 托盘保存配置引起的修改
+                }
+
+                info!("应用新配置: 热键={}, 模式={:?}", config.hotkey, config.runtime_mode);
+                clipboard::set_retry_policy(config.clipboard_retry.clone());
+                if config.ime.layout != s.config.ime.layout {
+                    ctx.english_hkl = paste::preload_english_layout(&config.ime.layout);
                 }
+                ctx.resolver = build_path_resolver(&config);
+                actions = ActionTable::from_config(&config);
+                presses.clear();
+                tray::request(TrayRequest::ApplyConfig(Box::new(config.clone())));
+
+                app_config = config.clone();
+                s.config = config;
             }
             // 托盘命令
             Some(cmd) = tray_rx.recv() => {
                 match cmd {
-                    TrayCommand::SwitchHotkey(ht) => {
-                        info!("主循环: 热键已切换为 {}", ht.display_name());
+                    TrayCommand::SwitchHotkey(combo) => {
+                        info!("主循环: 热键已切换为 {}", hotkey::display_name(&combo));
+                        let mut s = state.lock().await;
+                        s.config.hotkey = combo;
+                        actions = ActionTable::from_config(&s.config);
                     }
                     TrayCommand::SwitchMode(mode) => {
                         info!("主循环: 模式已切换为 {:?}", mode);
                         let mut s = state.lock().await;
-                        s.runtime_mode = mode;
+                        s.config.runtime_mode = mode;
+                    }
+                    TrayCommand::SwitchPathStyle(style) => {
+                        info!("主循环: 路径格式已切换为 {:?}", style);
+                        let mut s = state.lock().await;
+                        s.config.path_style = style;
                     }
                     TrayCommand::OpenFolder => {
                         if let Err(e) = tray::open_temp_folder() {
@@ -152,56 +273,476 @@ async fn main() -> Result<()> {
     std::process::exit(0);
 }
 
+/// 热键动作共用的资源（解析链与英文输入法随配置热重载更新）
+struct ActionContext<'a> {
+    clipboard_manager: &'a ClipboardManager,
+    save_tx: &'a mpsc::Sender<(PathBuf, Vec<u8>)>,
+    resolver: ChainResolver,
+    english_hkl: HKL,
+}
+
+/// 接收热重载结果；未启用监视时永远等待
+async fn recv_reload(
+    config_rx: &mut Option<mpsc::Receiver<Result<AppConfig>>>,
+) -> Option<Result<AppConfig>> {
+    match config_rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// 两份配置序列化后是否相同
+fn same_config(a: &AppConfig, b: &AppConfig) -> bool {
+    match (toml::to_string(a), toml::to_string(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// 触发动作的热键及当时的前台窗口
+struct Trigger {
+    hotkey_id: u32,
+    window: Option<WindowInfo>,
+}
+
+/// 读取当前配置，并按前台窗口应用 [[profile]] 覆盖
+async fn current_config(state: &Mutex<AppState>, window: Option<&WindowInfo>) -> AppConfig {
+    let config = state.lock().await.config.clone();
+    profile::resolve_config(&config, window)
+}
+
+/// 热键的按压跟踪器：只有启用双击的粘贴热键等待双击窗口
+fn press_tracker(config: &AppConfig, kind: ActionKind) -> PressTracker<SystemClock> {
+    let debounce = std::time::Duration::from_millis(config.debounce_ms);
+    let double_tap = match (kind, &config.double_tap_action) {
+        (ActionKind::Paste, DoubleTapAction::PasteHistory) => {
+            Some(std::time::Duration::from_millis(config.double_tap_ms))
+        }
+        _ => None,
+    };
+    PressTracker::new(SystemClock, debounce, double_tap)
+}
+
+/// 执行热键动作：配置依次应用 [[profile]] 与动作自身的覆盖项
+async fn run_action(
+    ctx: &ActionContext<'_>,
+    state: &Mutex<AppState>,
+    action: &HotkeyAction,
+    press: PressAction,
+    trigger: &Trigger,
+    history: &mut Vec<String>,
+) {
+    let mut config = current_config(state, trigger.window.as_ref()).await;
+    action.overrides.apply_to(&mut config);
+
+    let kind = action.action.for_press(press);
+    let result = match kind {
+        ActionKind::Paste => {
+            handle_paste(
+                ctx.clipboard_manager,
+                ctx.save_tx,
+                &config,
+                &ctx.resolver,
+                history,
+                ctx.english_hkl,
+                || plain_paste(&config, trigger),
+            )
+            .await
+        }
+        ActionKind::PasteHistory => {
+            paste_history(ctx.clipboard_manager, &config, &ctx.resolver, history, ctx.english_hkl).await
+        }
+        ActionKind::Reverse => handle_reverse(ctx.clipboard_manager, &config, ctx.english_hkl).await,
+    };
+
+    if let Err(e) = result {
+        error!("热键动作 {:?} 执行失败: {}", kind, e);
+    }
+}
+
+/// 剪贴板中没有图片和文件时的普通粘贴；前台窗口不在 fallback 范围内时转发原热键
+fn plain_paste(config: &AppConfig, trigger: &Trigger) -> Result<()> {
+    if !config.target.allows_fallback(trigger.window.as_ref()) {
+        info!("剪贴板无图片，前台窗口不在普通粘贴范围内，转发热键");
+        tray::request(TrayRequest::PassthroughHotkey(trigger.hotkey_id));
+        return Ok(());
+    }
+
+    info!("剪贴板无图片，执行普通粘贴");
+    paste::release_all_modifiers();
+    paste::send_paste_keystroke(config.paste_keystroke.keystroke()?)
+}
+
 /// 处理粘贴操作
 async fn handle_paste(
     clipboard_manager: &ClipboardManager,
     save_tx: &mpsc::Sender<(PathBuf, Vec<u8>)>,
-    mode: &RuntimeMode,
+    config: &AppConfig,
+    resolver: &dyn PathResolver,
+    history: &mut Vec<String>,
     english_hkl: HKL,
+    plain_paste: impl FnOnce() -> Result<()>,
 ) -> Result<()> {
     // 1. 检查剪贴板是否有图片
     if !clipboard_manager.has_image() {
         if clipboard_manager.has_file_list() {
-            if let Some(wsl_paths) = clipboard_manager.read_file_list_for_paste() {
-                let _ime_guard = match mode {
-                    RuntimeMode::Safe => Some(paste::ImeGuard::new(english_hkl)?),
-                    RuntimeMode::Fast => None,
-                };
+            let files = clipboard_manager.read_file_list_for_paste().unwrap_or_default();
+            let rendered = render_file_list(&files, config, resolver);
 
-                info!("粘贴文件路径: {}", wsl_paths);
-                paste::paste_text(&wsl_paths)?;
+            if !rendered.is_empty() {
+                let text = rendered.join(config.path_separator.as_str());
+                info!("粘贴文件路径: {}", text);
+                paste_text(clipboard_manager, config, &text, english_hkl).await?;
                 return Ok(());
             }
         }
 
-        info!("剪贴板无图片，执行普通粘贴");
-        paste::release_all_modifiers();
-        paste::send_ctrl_v()?;
-        return Ok(());
+        return plain_paste();
     }
 
     info!("检测到剪贴板图片");
 
     // 2. 读取图片（含缓存）
-    let (win_path, wsl_path, png_data) = clipboard_manager
+    let (win_path, png_data) = clipboard_manager
         .read_image_for_paste()
         .ok_or_else(|| anyhow::anyhow!("读取剪贴板图片失败"))?;
 
-    // 3. 输入法保护（仅安全模式）
-    let _ime_guard = match mode {
-        RuntimeMode::Safe => Some(paste::ImeGuard::new(english_hkl)?),
-        RuntimeMode::Fast => None,
+    // 内联格式：直接粘贴 PNG 的 Base64 编码，过大或需要逐字键入时回退为路径
+    if matches!(config.paste_format, PasteFormat::DataUri | PasteFormat::Base64) {
+        if config.paste_method == PasteMethod::Typing {
+            warn!("键入模式下不逐字输入内联图片，改为粘贴路径");
+        } else if png_data.len() as u64 <= config.inline_max_bytes {
+            if let Some(text) = inline::encode_png(&png_data, &config.paste_format) {
+                info!("粘贴内联图片: {} bytes → {} 字符", png_data.len(), text.len());
+                return paste_text(clipboard_manager, config, &text, english_hkl).await;
+            }
+        } else {
+            warn!(
+                "图片 {} bytes 超过内联上限 {}，改为粘贴路径",
+                png_data.len(),
+                config.inline_max_bytes
+            );
+        }
+    }
+
+    // 3. 项目附件模式：保存到仓库内的附件目录，粘贴相对项目根目录的路径
+    let (win_path, project_root) = match config.save_location {
+        SaveLocation::Temp => (win_path, None),
+        SaveLocation::Project => match attachment_target(&win_path, config) {
+            Some((attachment_path, root)) => (attachment_path, Some(root)),
+            None => {
+                warn!("未找到项目根目录，图片保存到临时目录");
+                (win_path, None)
+            }
+        },
     };
 
-    // 4. 粘贴 WSL 路径
-    info!("粘贴路径: {}", wsl_path);
-    paste::paste_text(&wsl_path)?;
+    // 附件模式：等待图片落盘（CF_HDROP 需指向已存在的文件），再以附件形式粘贴
+    if matches!(config.paste_format, PasteFormat::Attachment) {
+        image_saver::save_image(&win_path, &png_data).await?;
+        remember_image(history, &win_path, config.history_size);
+        info!("粘贴图片附件: {}", win_path.display());
+        let file_path = win_path.to_string_lossy();
+        return paste_preserving_clipboard(clipboard_manager, config, None, || {
+            paste::set_clipboard_attachment(&file_path, &png_data)
+        })
+        .await;
+    }
+
+    // 4. 按配置的路径格式生成粘贴文本
+    let win_path_str = win_path.to_string_lossy();
+    let styled_path = project_root
+        .and_then(|root| path::format_relative_path(&win_path_str, &root, config.path_style))
+        .unwrap_or_else(|| format_output_path(&win_path_str, config, resolver));
+    if styled_path.is_empty() {
+        anyhow::bail!("无法转换图片路径: {}", win_path.display());
+    }
+    let quoted = quote::quote_path_for(&styled_path, config.quote_style, config.path_separator);
+    let dimensions = template::png_dimensions(&png_data);
+    let wsl_path = wsl_path_for_template(&win_path_str, resolver);
+    let text = template::render(
+        &config.image_template,
+        &TemplateVars {
+            path: &quoted,
+            wsl_path: &wsl_path,
+            windows_path: &win_path_str,
+            name: file_name(&win_path_str),
+            width: dimensions.map(|(width, _)| width),
+            height: dimensions.map(|(_, height)| height),
+            size: Some(png_data.len() as u64),
+            index: 1,
+        },
+    );
 
-    // 5. 异步保存图片
+    // 5. 异步保存图片（先入队，避免等待恢复剪贴板期间目标程序读不到文件）
     info!("保存图片: {} bytes → {}", png_data.len(), win_path.display());
+    remember_image(history, &win_path, config.history_size);
     let _ = save_tx.send((win_path, png_data)).await;
 
-    // 6. ImeGuard 在此处 drop，触发 120ms 后恢复输入法
+    // 6. 粘贴路径（安全模式下切换输入法），目标程序读取后恢复原剪贴板
+    info!("粘贴路径: {}", text);
+    paste_text(clipboard_manager, config, &text, english_hkl).await
+}
+
+/// 按配置的粘贴方式输出文本，安全模式下输出期间切换到英文输入法
+async fn paste_text(
+    clipboard_manager: &ClipboardManager,
+    config: &AppConfig,
+    text: &str,
+    english_hkl: HKL,
+) -> Result<()> {
+    let ime_guard = match config.runtime_mode {
+        RuntimeMode::Safe => Some(paste::ImeGuard::new(english_hkl, &config.ime)?),
+        RuntimeMode::Fast => None,
+    };
+
+    match config.paste_method {
+        PasteMethod::Clipboard => {
+            paste_preserving_clipboard(clipboard_manager, config, ime_guard, || {
+                paste::set_clipboard_text(text)
+            })
+            .await
+        }
+        PasteMethod::Typing => {
+            type_text(config, text).await?;
+            drop(ime_guard);
+            Ok(())
+        }
+    }
+}
+
+/// 以 Unicode 按键事件逐批键入文本，不经过剪贴板
+async fn type_text(config: &AppConfig, text: &str) -> Result<()> {
+    let groups = typing::key_events(text);
+    let chunks = typing::chunk_events(&groups, config.typing_chunk_size);
+    let delay = tokio::time::Duration::from_millis(config.typing_chunk_delay_ms);
+
+    // 热键的修饰键可能仍处于按下状态，先释放，避免与键入字符组合
+    paste::release_all_modifiers();
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        if index > 0 && !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        paste::send_key_events(chunk)?;
+    }
+
+    Ok(())
+}
+
+/// 写入剪贴板并发送粘贴快捷键，按配置在目标程序读取后恢复原剪贴板内容
+///
+/// 恢复前若剪贴板已被其他程序改写（序列号变化），则放弃恢复，避免覆盖用户新复制的内容。
+/// `ime_guard` 在粘贴快捷键发出后即释放，不等待剪贴板恢复。
+async fn paste_preserving_clipboard(
+    clipboard_manager: &ClipboardManager,
+    config: &AppConfig,
+    ime_guard: Option<paste::ImeGuard>,
+    set_clipboard: impl FnOnce() -> Result<()>,
+) -> Result<()> {
+    let snapshot = if config.restore_clipboard {
+        clipboard_manager.snapshot()
+    } else {
+        None
+    };
+
+    set_clipboard()?;
+    paste::send_paste_keystroke(config.paste_keystroke.keystroke()?)?;
+    drop(ime_guard);
+
+    let Some(snapshot) = snapshot else {
+        return Ok(());
+    };
+
+    // Ctrl+V 由目标程序异步处理，等待其读取剪贴板后再恢复
+    let pasted_seq = clipboard_manager.get_sequence();
+    tokio::time::sleep(tokio::time::Duration::from_millis(config.clipboard_restore_delay_ms)).await;
+
+    if clipboard_manager.get_sequence() != pasted_seq {
+        info!("剪贴板已被其他程序修改，跳过恢复");
+        return Ok(());
+    }
+
+    if let Err(e) = clipboard_manager.restore(&snapshot) {
+        warn!("恢复剪贴板失败: {}", e);
+    }
+    Ok(())
+}
+
+/// 按 file_template 渲染文件列表中的每个路径，跳过无法转换的路径
+fn render_file_list(files: &[String], config: &AppConfig, resolver: &dyn PathResolver) -> Vec<String> {
+    files
+        .iter()
+        .filter_map(|win_path| {
+            let styled = format_output_path(win_path, config, resolver);
+            (!styled.is_empty()).then_some((win_path, styled))
+        })
+        .enumerate()
+        .map(|(index, (win_path, styled))| {
+            let quoted = quote::quote_path_for(&styled, config.quote_style, config.path_separator);
+            let wsl_path = wsl_path_for_template(win_path, resolver);
+            template::render(
+                &config.file_template,
+                &TemplateVars {
+                    path: &quoted,
+                    wsl_path: &wsl_path,
+                    windows_path: win_path,
+                    name: file_name(win_path),
+                    size: std::fs::metadata(win_path).ok().map(|meta| meta.len()),
+                    index: index + 1,
+                    ..TemplateVars::default()
+                },
+            )
+        })
+        .collect()
+}
+
+/// 记录已粘贴的图片路径，超出上限时丢弃最旧的记录
+fn remember_image(history: &mut Vec<String>, win_path: &Path, limit: usize) {
+    let win_path = win_path.to_string_lossy().into_owned();
+    history.retain(|path| *path != win_path);
+    history.push(win_path);
+    if history.len() > limit {
+        history.drain(..history.len() - limit);
+    }
+}
+
+/// 双击动作：按文件列表模板粘贴历史队列中的所有图片（由旧到新）
+async fn paste_history(
+    clipboard_manager: &ClipboardManager,
+    config: &AppConfig,
+    resolver: &dyn PathResolver,
+    history: &[String],
+    english_hkl: HKL,
+) -> Result<()> {
+    let rendered = render_file_list(history, config, resolver);
+    if rendered.is_empty() {
+        info!("图片历史为空，跳过");
+        return Ok(());
+    }
+
+    let text = rendered.join(config.path_separator.as_str());
+    info!("粘贴图片历史 ({} 项): {}", rendered.len(), text);
+    paste_text(clipboard_manager, config, &text, english_hkl).await
+}
+
+/// 模板中 `{wsl_path}` 的值：不受 path_style 影响的 WSL 路径
+fn wsl_path_for_template(win_path: &str, resolver: &dyn PathResolver) -> String {
+    resolver
+        .to_wsl(&path::normalize_windows_path(win_path))
+        .unwrap_or_default()
+}
+
+fn file_name(win_path: &str) -> &str {
+    win_path.rsplit(['\\', '/']).next().unwrap_or(win_path)
+}
+
+/// 按配置构建 WSL 路径解析链
+fn build_path_resolver(config: &AppConfig) -> ChainResolver {
+    ChainResolver::from_config(
+        &config.path_resolvers,
+        &config.path_mappings,
+        config.resolver_command.as_deref(),
+        std::time::Duration::from_millis(config.resolver_timeout_ms),
+    )
+}
+
+/// 按配置解析并格式化要粘贴的 Windows 路径
+fn format_output_path(win_path: &str, config: &AppConfig, resolver: &dyn PathResolver) -> String {
+    let win_path = resolve_windows_path(win_path, config);
+
+    if config.relative_paths {
+        if let Some(root) = project_root_windows(config) {
+            if let Some(relative) = path::format_relative_path(&win_path, &root, config.path_style) {
+                return relative;
+            }
+        }
+    }
+
+    path::format_path(&win_path, config.path_style, resolver)
+}
+
+fn resolve_windows_path(win_path: &str, config: &AppConfig) -> String {
+    match config.path_resolution {
+        PathResolution::Logical => win_path.to_string(),
+        PathResolution::Physical => {
+            path::resolve_physical_path(win_path, &canonical::Win32Canonicalizer)
+        }
+    }
+}
+
+/// 配置的项目根目录（WSL 路径先转换为 Windows 路径）
+fn project_root_windows(config: &AppConfig) -> Option<String> {
+    let root = config.project_root.as_deref()?.trim();
+    let distro = resolver::wsl_distro(config.wsl_distro.as_deref());
+    let root = path::convert_path_to_windows(root, &config.path_mappings, distro.as_deref());
+
+    if root.is_empty() {
+        warn!("无法识别项目根目录: {:?}", config.project_root);
+        return None;
+    }
+
+    Some(resolve_windows_path(&root, config))
+}
+
+/// 项目附件的保存路径与项目根目录
+///
+/// 根目录优先取配置，其次从前台终端标题中的工作目录向上查找 Git 仓库。
+fn attachment_target(win_path: &Path, config: &AppConfig) -> Option<(PathBuf, String)> {
+    let root = project_root_windows(config).or_else(|| {
+        let title = window::foreground_window_title()?;
+        let candidate = project::path_from_window_title(&title)?;
+        let distro = resolver::wsl_distro(config.wsl_distro.as_deref());
+        let cwd = path::convert_path_to_windows(&candidate, &config.path_mappings, distro.as_deref());
+        if cwd.is_empty() {
+            return None;
+        }
+        project::find_git_root(Path::new(&cwd)).map(|root| root.to_string_lossy().into_owned())
+    })?;
+
+    if let Err(e) = project::ensure_git_exclude(Path::new(&root), &config.attachment_dir) {
+        warn!("写入 .git/info/exclude 失败: {}", e);
+    }
+
+    let file_name = win_path.file_name()?;
+    let attachment_path = Path::new(&root).join(&config.attachment_dir).join(file_name);
+    info!("项目附件: {}", attachment_path.display());
+    Some((attachment_path, root))
+}
+
+/// 反向转换：将剪贴板中的 WSL 路径转换为 Windows 路径后粘贴，或以文件形式放入剪贴板
+async fn handle_reverse(
+    clipboard_manager: &ClipboardManager,
+    config: &AppConfig,
+    english_hkl: HKL,
+) -> Result<()> {
+    let text = clipboard_manager
+        .read_text()
+        .ok_or_else(|| anyhow::anyhow!("剪贴板中没有文本"))?;
+
+    let distro = resolver::wsl_distro(config.wsl_distro.as_deref());
+    let paths: Vec<String> = path::split_path_list(&text)
+        .into_iter()
+        .map(|line| path::convert_path_to_windows(line, &config.path_mappings, distro.as_deref()))
+        .filter(|path| !path.is_empty())
+        .collect();
+
+    if paths.is_empty() {
+        warn!("剪贴板文本中没有可转换的 WSL 路径");
+        return Ok(());
+    }
+
+    match config.reverse_output {
+        ReverseOutput::Text => {
+            let text = quote::join_paths(&paths, config.reverse_quote_style, config.path_separator);
+            info!("粘贴 Windows 路径: {}", text);
+            paste_text(clipboard_manager, config, &text, english_hkl).await?;
+        }
+        ReverseOutput::Files => {
+            paste::set_clipboard_files(&paths)?;
+            info!("已将 {} 个文件放入剪贴板", paths.len());
+        }
+    }
 
     Ok(())
 }
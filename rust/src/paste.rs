@@ -1,31 +1,47 @@
-use anyhow::{bail, Result};
-use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
-use windows::Win32::System::DataExchange::{
-    CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
-};
+use anyhow::{anyhow, bail, Result};
+use global_hotkey::hotkey::Modifiers;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::time::{Duration, Instant};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{GlobalFree, HWND, LPARAM, POINT, RECT, WPARAM};
+use windows::Win32::System::DataExchange::{RegisterClipboardFormatW, SetClipboardData};
 use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
-use windows::Win32::System::Ole::CF_UNICODETEXT;
+use windows::Win32::System::Ole::{CF_DIB, CF_HDROP, CF_UNICODETEXT};
 use windows::Win32::UI::Input::KeyboardAndMouse::{
-    SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, VIRTUAL_KEY, VK_CONTROL,
-    VK_LMENU, VK_MENU, VK_SHIFT, VK_V,
+    SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, INPUT_MOUSE, KEYBDINPUT, KEYEVENTF_EXTENDEDKEY,
+    KEYEVENTF_KEYUP, KEYEVENTF_UNICODE, MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP, MOUSEINPUT, MOUSE_EVENT_FLAGS,
+    VIRTUAL_KEY, VK_CONTROL, VK_LMENU, VK_LWIN, VK_MENU, VK_SHIFT,
 };
 use windows::Win32::UI::WindowsAndMessaging::{
-    GetForegroundWindow, GetWindowThreadProcessId, PostMessageW,
+    GetCursorPos, GetForegroundWindow, GetWindowRect, GetWindowThreadProcessId, PostMessageW,
+    SendMessageTimeoutW, SetCursorPos, SMTO_ABORTIFHUNG,
 };
 
 use tracing::{info, warn};
 
+use crate::clipboard::ClipboardSession;
+use crate::config::{ImeConfig, ImeSwitchMode};
+use crate::hotkey;
+use crate::typing::KeyEvent;
+
 /// HKL 类型别名（Win32 HKL 就是一个 isize）
 pub type HKL = isize;
 
 /// WM_INPUTLANGCHANGEREQUEST
 const WM_INPUTLANGCHANGEREQUEST: u32 = 0x0050;
 
-/// 预加载英文输入法布局，返回英文 HKL
+/// WM_IME_CONTROL 及其子命令
+const WM_IME_CONTROL: u32 = 0x0283;
+const IMC_GETCONVERSIONMODE: usize = 0x0001;
+const IMC_SETCONVERSIONMODE: usize = 0x0002;
+const IMC_GETOPENSTATUS: usize = 0x0005;
+const IMC_SETOPENSTATUS: usize = 0x0006;
+
+/// 预加载英文输入法布局（如 "00000409"），返回英文 HKL
 /// 启动时调用一次即可
-pub fn preload_english_layout() -> HKL {
+pub fn preload_english_layout(layout: &str) -> HKL {
     unsafe {
-        let layout_str: Vec<u16> = "00000409\0".encode_utf16().collect();
+        let layout_str: Vec<u16> = layout.encode_utf16().chain(std::iter::once(0)).collect();
         // KLF_ACTIVATE (0x1): 加载并激活英文布局，确保可用（与 AHK 一致）
         LoadKeyboardLayoutW(layout_str.as_ptr(), 0x01)
     }
@@ -37,63 +53,187 @@ extern "system" {
     fn GetKeyboardLayout(idThread: u32) -> HKL;
 }
 
-/// 粘贴文本到剪贴板并执行粘贴操作
-pub fn paste_text(text: &str) -> Result<()> {
-    unsafe {
-        // 尝试打开剪贴板，带重试机制
-        if OpenClipboard(None).is_err() {
-            std::thread::sleep(std::time::Duration::from_millis(10));
-            if let Err(e) = OpenClipboard(None) {
-                bail!("无法打开剪贴板: {:?}", e);
-            }
+#[link(name = "imm32")]
+extern "system" {
+    fn ImmGetDefaultIMEWnd(hwnd: HWND) -> HWND;
+}
+
+/// 将文本写入剪贴板（CF_UNICODETEXT）
+pub fn set_clipboard_text(text: &str) -> Result<()> {
+    // 直接生成 UTF-16LE 字节（含结尾 NUL），避免大文本（如内联图片）产生多份副本
+    let mut bytes = Vec::with_capacity((text.len() + 1) * 2);
+    for unit in text.encode_utf16().chain(std::iter::once(0)) {
+        bytes.extend_from_slice(&unit.to_le_bytes());
+    }
+
+    write_clipboard(&[(CF_UNICODETEXT.0 as u32, &bytes)])
+}
+
+/// 将文件列表写入剪贴板（CF_HDROP），可直接在资源管理器等应用中粘贴
+pub fn set_clipboard_files(paths: &[String]) -> Result<()> {
+    if paths.is_empty() {
+        bail!("文件列表为空");
+    }
+
+    let hdrop = build_hdrop(paths);
+    // Preferred DropEffect = DROPEFFECT_COPY，避免资源管理器按剪切处理
+    let drop_effect = 1u32.to_le_bytes();
+    let effect_format = register_clipboard_format("Preferred DropEffect");
+
+    let mut entries: Vec<(u32, &[u8])> = vec![(CF_HDROP.0 as u32, &hdrop)];
+    if effect_format != 0 {
+        entries.push((effect_format, &drop_effect));
+    }
+    write_clipboard(&entries)
+}
+
+/// 以附件形式写入剪贴板：CF_DIB + "PNG" + CF_HDROP 同时提供
+///
+/// 对应 `scripts/set-clipboard-attachment.ps1`：部分客户端读取位图，部分 Electron/TUI
+/// 客户端优先读取 PNG 原始流，其余通过文件通道识别附件。文件需已写入磁盘。
+pub fn set_clipboard_attachment(file_path: &str, png_data: &[u8]) -> Result<()> {
+    let hdrop = build_hdrop(&[file_path.to_string()]);
+    let png_format = register_clipboard_format("PNG");
+
+    let mut entries: Vec<(u32, &[u8])> = Vec::new();
+
+    let dib = png_to_dib(png_data);
+    match dib {
+        Some(ref dib) => entries.push((CF_DIB.0 as u32, dib)),
+        None => warn!("PNG 解码失败，附件不含 CF_DIB"),
+    }
+    if png_format != 0 {
+        entries.push((png_format, png_data));
+    }
+    entries.push((CF_HDROP.0 as u32, &hdrop));
+
+    write_clipboard(&entries)
+}
+
+/// 将 PNG 解码为 32 位 BI_RGB 的 DIB（BITMAPINFOHEADER + 自下而上的 BGRA 像素）
+fn png_to_dib(png_data: &[u8]) -> Option<Vec<u8>> {
+    let mut decoder = png::Decoder::new(std::io::Cursor::new(png_data));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().ok()?;
+
+    let mut buffer = vec![0u8; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer).ok()?;
+    let pixels = &buffer[..frame.buffer_size()];
+
+    let to_rgba: fn(&[u8]) -> [u8; 4] = match frame.color_type {
+        png::ColorType::Rgba => |p| [p[0], p[1], p[2], p[3]],
+        png::ColorType::Rgb => |p| [p[0], p[1], p[2], 255],
+        png::ColorType::GrayscaleAlpha => |p| [p[0], p[0], p[0], p[1]],
+        png::ColorType::Grayscale => |p| [p[0], p[0], p[0], 255],
+        png::ColorType::Indexed => return None,
+    };
+    let channels = frame.color_type.samples();
+
+    let width = frame.width as usize;
+    let rgba: Vec<u8> = pixels
+        .chunks_exact(frame.line_size)
+        .take(frame.height as usize)
+        .flat_map(|line| line[..width * channels].chunks_exact(channels).map(to_rgba))
+        .flatten()
+        .collect();
+
+    build_dib(&rgba, frame.width, frame.height)
+}
+
+/// 由自上而下的 RGBA 像素构造 CF_DIB 数据
+fn build_dib(rgba: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
+    const HEADER_SIZE: u32 = 40;
+
+    let image_size = width.checked_mul(height)?.checked_mul(4)?;
+    if image_size == 0 || rgba.len() != image_size as usize {
+        return None;
+    }
+    let mut data = Vec::with_capacity(HEADER_SIZE as usize + rgba.len());
+
+    // BITMAPINFOHEADER：正高度表示自下而上，兼容性最好
+    data.extend_from_slice(&HEADER_SIZE.to_le_bytes());
+    data.extend_from_slice(&(width as i32).to_le_bytes());
+    data.extend_from_slice(&(height as i32).to_le_bytes());
+    data.extend_from_slice(&1u16.to_le_bytes());
+    data.extend_from_slice(&32u16.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes()); // BI_RGB
+    data.extend_from_slice(&image_size.to_le_bytes());
+    data.extend_from_slice(&0i32.to_le_bytes());
+    data.extend_from_slice(&0i32.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+
+    for row in rgba.chunks_exact(width as usize * 4).rev() {
+        for pixel in row.chunks_exact(4) {
+            data.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
         }
+    }
+
+    Some(data)
+}
 
-        // 清空剪贴板（关键修复：必须先清空再设置）
-        if let Err(e) = EmptyClipboard() {
-            CloseClipboard().ok();
-            bail!("清空剪贴板失败: {:?}", e);
+/// 构造 DROPFILES 结构 + 以双 NUL 结尾的 UTF-16 路径列表
+fn build_hdrop(paths: &[String]) -> Vec<u8> {
+    // DROPFILES { pFiles, pt.x, pt.y, fNC, fWide }
+    const DROPFILES_SIZE: u32 = 20;
+
+    let mut data = Vec::new();
+    data.extend_from_slice(&DROPFILES_SIZE.to_le_bytes());
+    data.extend_from_slice(&0i32.to_le_bytes());
+    data.extend_from_slice(&0i32.to_le_bytes());
+    data.extend_from_slice(&0i32.to_le_bytes());
+    data.extend_from_slice(&1i32.to_le_bytes());
+
+    for path in paths {
+        for unit in path.encode_utf16().chain(std::iter::once(0)) {
+            data.extend_from_slice(&unit.to_le_bytes());
         }
+    }
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data
+}
 
-        // 准备 UTF-16 编码的数据
-        let utf16: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
-        let byte_len = utf16.len() * std::mem::size_of::<u16>();
+fn register_clipboard_format(name: &str) -> u32 {
+    let name_w: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe { RegisterClipboardFormatW(PCWSTR::from_raw(name_w.as_ptr())) }
+}
 
-        // 分配内存
-        let h_mem = match GlobalAlloc(GMEM_MOVEABLE, byte_len) {
-            Ok(mem) => mem,
-            Err(e) => {
-                CloseClipboard().ok();
-                bail!("分配剪贴板内存失败: {:?}", e);
-            }
-        };
+/// 打开并清空剪贴板，依次写入各格式数据
+pub fn write_clipboard(entries: &[(u32, &[u8])]) -> Result<()> {
+    let session = ClipboardSession::open()?;
 
-        let ptr = GlobalLock(h_mem);
-        if ptr.is_null() {
-            CloseClipboard().ok();
-            bail!("锁定内存失败");
-        }
+    // 清空剪贴板（关键修复：必须先清空再设置）
+    session.empty()?;
 
-        // 复制数据
-        std::ptr::copy_nonoverlapping(utf16.as_ptr() as *const u8, ptr as *mut u8, byte_len);
+    unsafe {
+        for (format, data) in entries {
+            // 分配内存
+            let h_mem = match GlobalAlloc(GMEM_MOVEABLE, data.len()) {
+                Ok(mem) => mem,
+                Err(e) => bail!("分配剪贴板内存失败: {:?}", e),
+            };
+
+            let ptr = GlobalLock(h_mem);
+            if ptr.is_null() {
+                let _ = GlobalFree(h_mem);
+                bail!("锁定内存失败");
+            }
 
-        let _ = GlobalUnlock(h_mem);
+            // 复制数据
+            std::ptr::copy_nonoverlapping(data.as_ptr(), ptr as *mut u8, data.len());
 
-        // 设置剪贴板数据
-        if SetClipboardData(
-            CF_UNICODETEXT.0 as u32,
-            windows::Win32::Foundation::HANDLE(h_mem.0 as isize),
-        )
-        .is_err()
-        {
-            CloseClipboard().ok();
-            bail!("设置剪贴板数据失败");
-        }
+            let _ = GlobalUnlock(h_mem);
 
-        CloseClipboard().ok();
+            // 设置剪贴板数据（成功后内存归系统所有）
+            if SetClipboardData(*format, windows::Win32::Foundation::HANDLE(h_mem.0 as isize))
+                .is_err()
+            {
+                let _ = GlobalFree(h_mem);
+                bail!("设置剪贴板数据失败: format={}", format);
+            }
+        }
     }
 
-    // 发送粘贴快捷键
-    send_ctrl_v()?;
     Ok(())
 }
 
@@ -111,16 +251,186 @@ pub fn release_all_modifiers() {
     }
 }
 
-/// 发送 Ctrl+V 粘贴快捷键（使用 SendInput 替代 keybd_event）
-pub fn send_ctrl_v() -> Result<()> {
+/// 粘贴按键：按热键语法解析的组合键，或鼠标右键（mintty / conhost 的右键粘贴）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasteKeystroke {
+    Keys {
+        modifiers: Vec<VIRTUAL_KEY>,
+        key: VIRTUAL_KEY,
+        /// 导航键等需要带 KEYEVENTF_EXTENDEDKEY 发送
+        extended: bool,
+    },
+    RightClick,
+}
+
+/// 配置中的粘贴按键，读取配置时解析一次
+///
+/// 无效的值不会导致整个配置读取失败：粘贴时报错，由 `AppConfig::validate` 报告。
+#[derive(Debug, Clone)]
+pub struct PasteKeystrokeSpec {
+    spec: String,
+    parsed: Result<PasteKeystroke, String>,
+}
+
+impl PasteKeystrokeSpec {
+    pub fn new(spec: impl Into<String>) -> Self {
+        let spec = spec.into();
+        let parsed = PasteKeystroke::parse(&spec).map_err(|e| format!("{:#}", e));
+        if let Err(e) = &parsed {
+            warn!("粘贴按键无效 {}: {}", spec, e);
+        }
+        Self { spec, parsed }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.spec
+    }
+
+    /// 解析结果，无效时返回错误
+    pub fn keystroke(&self) -> Result<&PasteKeystroke> {
+        self.parsed
+            .as_ref()
+            .map_err(|e| anyhow!("粘贴按键 {} 无效: {}", self.spec, e))
+    }
+}
+
+impl Serialize for PasteKeystrokeSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.spec)
+    }
+}
+
+impl<'de> Deserialize<'de> for PasteKeystrokeSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+impl PasteKeystroke {
+    /// 解析配置值，如 "^v"、"Ctrl+Shift+V"、"Shift+Insert"、"RightClick"
+    pub fn parse(spec: &str) -> Result<Self> {
+        if matches!(spec.trim().to_lowercase().as_str(), "rightclick" | "right_click" | "rbutton") {
+            return Ok(PasteKeystroke::RightClick);
+        }
+
+        let (mods, code) = hotkey::parse_hotkey(spec)?;
+        let key = hotkey::code_to_vk(code)
+            .ok_or_else(|| anyhow::anyhow!("不支持作为粘贴按键: {}", spec))?;
+
+        let modifiers = [
+            (Modifiers::CONTROL, VK_CONTROL),
+            (Modifiers::ALT, VK_MENU),
+            (Modifiers::SHIFT, VK_SHIFT),
+            (Modifiers::SUPER, VK_LWIN),
+        ]
+        .into_iter()
+        .filter(|(modifier, _)| mods.contains(*modifier))
+        .map(|(_, vk)| vk)
+        .collect();
+
+        Ok(PasteKeystroke::Keys {
+            modifiers,
+            key: VIRTUAL_KEY(key),
+            extended: hotkey::is_extended_key(code),
+        })
+    }
+}
+
+/// 发送粘贴按键（使用 SendInput 替代 keybd_event）
+pub fn send_paste_keystroke(keystroke: &PasteKeystroke) -> Result<()> {
     release_all_modifiers();
 
-    let inputs = [
-        make_key_input(VK_CONTROL, false), // Ctrl down
-        make_key_input(VK_V, false),       // V down
-        make_key_input(VK_V, true),        // V up
-        make_key_input(VK_CONTROL, true),  // Ctrl up
-    ];
+    let inputs: Vec<INPUT> = match keystroke {
+        PasteKeystroke::Keys {
+            modifiers,
+            key,
+            extended,
+        } => modifiers
+            .iter()
+            .map(|&vk| make_key_input(vk, false))
+            .chain([
+                make_extended_key_input(*key, false, *extended),
+                make_extended_key_input(*key, true, *extended),
+            ])
+            .chain(modifiers.iter().rev().map(|&vk| make_key_input(vk, true)))
+            .collect(),
+        PasteKeystroke::RightClick => return send_right_click(),
+    };
+
+    unsafe {
+        let sent = SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+        if sent != inputs.len() as u32 {
+            bail!("SendInput 发送失败，期望 {} 实际 {}", inputs.len(), sent);
+        }
+    }
+
+    Ok(())
+}
+
+/// 在前台窗口内单击鼠标右键；光标不在窗口内时临时移到窗口中心，点击后移回
+fn send_right_click() -> Result<()> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        let mut rect = RECT::default();
+        let mut cursor = POINT::default();
+        GetCursorPos(&mut cursor)?;
+
+        let inside = hwnd.0 == 0
+            || GetWindowRect(hwnd, &mut rect).is_err()
+            || (cursor.x >= rect.left
+                && cursor.x < rect.right
+                && cursor.y >= rect.top
+                && cursor.y < rect.bottom);
+        if !inside {
+            SetCursorPos((rect.left + rect.right) / 2, (rect.top + rect.bottom) / 2)?;
+        }
+
+        let inputs = [
+            make_mouse_input(MOUSEEVENTF_RIGHTDOWN),
+            make_mouse_input(MOUSEEVENTF_RIGHTUP),
+        ];
+        let sent = SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+
+        if !inside {
+            // 等待输入队列处理完点击再恢复光标位置
+            std::thread::sleep(std::time::Duration::from_millis(30));
+            let _ = SetCursorPos(cursor.x, cursor.y);
+        }
+
+        if sent != inputs.len() as u32 {
+            bail!("SendInput 发送失败，期望 {} 实际 {}", inputs.len(), sent);
+        }
+    }
+
+    Ok(())
+}
+
+/// 构造鼠标 INPUT 结构（在当前光标位置）
+fn make_mouse_input(flags: MOUSE_EVENT_FLAGS) -> INPUT {
+    INPUT {
+        r#type: INPUT_MOUSE,
+        Anonymous: INPUT_0 {
+            mi: MOUSEINPUT {
+                dx: 0,
+                dy: 0,
+                mouseData: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    }
+}
+
+/// 发送一批模拟键入事件（一次 SendInput 调用）
+pub fn send_key_events(events: &[KeyEvent]) -> Result<()> {
+    let inputs: Vec<INPUT> = events
+        .iter()
+        .map(|event| match *event {
+            KeyEvent::Unicode { unit, key_up } => make_unicode_input(unit, key_up),
+            KeyEvent::VirtualKey { vk, key_up } => make_key_input(VIRTUAL_KEY(vk), key_up),
+        })
+        .collect();
 
     unsafe {
         let sent = SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
@@ -132,11 +442,40 @@ pub fn send_ctrl_v() -> Result<()> {
     Ok(())
 }
 
-/// 构造键盘 INPUT 结构
+/// 构造 KEYEVENTF_UNICODE 键盘 INPUT 结构（wVk 为 0，字符放在 wScan）
+fn make_unicode_input(unit: u16, key_up: bool) -> INPUT {
+    let mut flags = KEYEVENTF_UNICODE;
+    if key_up {
+        flags |= KEYEVENTF_KEYUP;
+    }
+
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VIRTUAL_KEY(0),
+                wScan: unit,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    }
+}
+
+/// 构造键盘 INPUT 结构（Win 键按扩展键发送）
 fn make_key_input(vk: VIRTUAL_KEY, key_up: bool) -> INPUT {
+    make_extended_key_input(vk, key_up, vk == VK_LWIN)
+}
+
+/// 构造键盘 INPUT 结构，`extended` 为 true 时带 KEYEVENTF_EXTENDEDKEY
+fn make_extended_key_input(vk: VIRTUAL_KEY, key_up: bool, extended: bool) -> INPUT {
     let mut flags = windows::Win32::UI::Input::KeyboardAndMouse::KEYBD_EVENT_FLAGS(0);
     if key_up {
-        flags = KEYEVENTF_KEYUP;
+        flags |= KEYEVENTF_KEYUP;
+    }
+    if extended {
+        flags |= KEYEVENTF_EXTENDEDKEY;
     }
 
     INPUT {
@@ -154,83 +493,249 @@ fn make_key_input(vk: VIRTUAL_KEY, key_up: bool) -> INPUT {
 }
 
 /// 输入法保护器
-/// 在粘贴路径前切换到英文输入法，完成后异步恢复
+/// 在粘贴路径前切换到英文输入，完成后异步恢复
 pub struct ImeGuard {
-    /// 切换前的键盘布局句柄
-    previous_hkl: HKL,
-    /// 前台窗口句柄
-    hwnd: HWND,
+    /// 需要恢复的状态
+    restore: ImeRestore,
+    /// 粘贴后延迟多久恢复
+    restore_delay: Duration,
+}
+
+/// 切换前的输入法状态
+#[derive(Clone, Copy)]
+enum ImeRestore {
+    None,
+    /// 前台窗口与切换前的键盘布局
+    Layout { hwnd: HWND, hkl: HKL },
+    /// 默认 IME 窗口与切换前的打开状态、转换模式
+    ImeStatus {
+        ime_hwnd: HWND,
+        open: isize,
+        conversion: isize,
+    },
 }
 
 impl ImeGuard {
     /// 创建新的输入法保护器
-    /// 获取当前输入法布局，切换到英文，保存旧布局用于恢复
-    pub fn new(english_hkl: HKL) -> Result<Self> {
-        unsafe {
+    /// 按配置切换键盘布局或关闭输入法，确认切换生效后返回，保存旧状态用于恢复
+    pub fn new(english_hkl: HKL, config: &ImeConfig) -> Result<Self> {
+        let restore = unsafe {
             let hwnd = GetForegroundWindow();
             if hwnd.0 == 0 {
                 // 无前台窗口，跳过输入法切换
-                return Ok(Self {
-                    previous_hkl: 0,
-                    hwnd: HWND::default(),
-                });
+                ImeRestore::None
+            } else {
+                match config.mode {
+                    ImeSwitchMode::Layout => switch_layout(hwnd, english_hkl, config),
+                    ImeSwitchMode::ImeStatus => close_ime(hwnd, config),
+                }
             }
+        };
 
-            let thread_id = GetWindowThreadProcessId(hwnd, None);
-            if thread_id == 0 {
-                return Ok(Self {
-                    previous_hkl: 0,
-                    hwnd: HWND::default(),
-                });
-            }
+        Ok(Self {
+            restore,
+            restore_delay: Duration::from_millis(config.restore_delay_ms),
+        })
+    }
+}
 
-            let current_hkl = GetKeyboardLayout(thread_id);
-
-            // 仅在当前布局不是英文时才切换
-            if english_hkl != 0 && current_hkl != english_hkl {
-                info!("ImeGuard: 切换输入法 {:#x} -> {:#x}", current_hkl, english_hkl);
-                let _ = PostMessageW(
-                    hwnd,
-                    WM_INPUTLANGCHANGEREQUEST,
-                    WPARAM(0),
-                    LPARAM(english_hkl),
-                );
-                std::thread::sleep(std::time::Duration::from_millis(60));
-            }
+/// 切换到英文键盘布局（WM_INPUTLANGCHANGEREQUEST）
+unsafe fn switch_layout(hwnd: HWND, english_hkl: HKL, config: &ImeConfig) -> ImeRestore {
+    let thread_id = GetWindowThreadProcessId(hwnd, None);
+    if thread_id == 0 {
+        return ImeRestore::None;
+    }
 
-            Ok(Self {
-                previous_hkl: current_hkl,
-                hwnd,
-            })
+    let current_hkl = GetKeyboardLayout(thread_id);
+
+    // 仅在当前布局不是英文时才切换
+    if english_hkl != 0 && current_hkl != english_hkl {
+        info!("ImeGuard: 切换输入法 {:#x} -> {:#x}", current_hkl, english_hkl);
+        let _ = PostMessageW(
+            hwnd,
+            WM_INPUTLANGCHANGEREQUEST,
+            WPARAM(0),
+            LPARAM(english_hkl),
+        );
+
+        let switched = wait_until(config, || GetKeyboardLayout(thread_id) == english_hkl);
+        if !switched {
+            warn!("ImeGuard: 键盘布局切换未生效 (当前 {:#x})", GetKeyboardLayout(thread_id));
         }
     }
+
+    ImeRestore::Layout {
+        hwnd,
+        hkl: current_hkl,
+    }
+}
+
+/// 关闭输入法并切到字母数字转换模式（WM_IME_CONTROL），布局保持不变
+unsafe fn close_ime(hwnd: HWND, config: &ImeConfig) -> ImeRestore {
+    let ime_hwnd = ImmGetDefaultIMEWnd(hwnd);
+    if ime_hwnd.0 == 0 {
+        return ImeRestore::None;
+    }
+
+    let (Some(open), Some(conversion)) = (
+        ime_control(ime_hwnd, IMC_GETOPENSTATUS, 0),
+        ime_control(ime_hwnd, IMC_GETCONVERSIONMODE, 0),
+    ) else {
+        warn!("ImeGuard: 查询输入法状态失败");
+        return ImeRestore::None;
+    };
+
+    // IME_CMODE_NATIVE (0x1) 为 0 表示字母数字模式
+    if open == 0 && conversion & 0x1 == 0 {
+        return ImeRestore::None;
+    }
+
+    info!("ImeGuard: 关闭输入法 (open={}, conversion={:#x})", open, conversion);
+    ime_control(ime_hwnd, IMC_SETCONVERSIONMODE, conversion & !0x1);
+    ime_control(ime_hwnd, IMC_SETOPENSTATUS, 0);
+
+    let switched = wait_until(config, || ime_control(ime_hwnd, IMC_GETOPENSTATUS, 0) == Some(0));
+    if !switched {
+        warn!("ImeGuard: 输入法关闭未生效");
+    }
+
+    ImeRestore::ImeStatus {
+        ime_hwnd,
+        open,
+        conversion,
+    }
+}
+
+/// 向默认 IME 窗口发送 WM_IME_CONTROL，目标窗口无响应时超时返回 None
+unsafe fn ime_control(ime_hwnd: HWND, command: usize, value: isize) -> Option<isize> {
+    let mut result = 0usize;
+    let sent = SendMessageTimeoutW(
+        ime_hwnd,
+        WM_IME_CONTROL,
+        WPARAM(command),
+        LPARAM(value),
+        SMTO_ABORTIFHUNG,
+        100,
+        Some(&mut result),
+    );
+    (sent.0 != 0).then_some(result as isize)
+}
+
+/// 至少等待 switch_delay_ms，然后轮询直到条件满足或超过 verify_timeout_ms
+fn wait_until(config: &ImeConfig, mut done: impl FnMut() -> bool) -> bool {
+    std::thread::sleep(Duration::from_millis(config.switch_delay_ms));
+
+    let deadline = Instant::now() + Duration::from_millis(config.verify_timeout_ms);
+    loop {
+        if done() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
 }
 
 impl Drop for ImeGuard {
-    /// 析构时异步恢复之前的键盘布局
+    /// 析构时异步恢复之前的输入法状态
     fn drop(&mut self) {
-        if self.previous_hkl == 0 || self.hwnd.0 == 0 {
+        let restore = self.restore;
+        if matches!(restore, ImeRestore::None) {
             return;
         }
 
-        let hkl = self.previous_hkl;
-        let hwnd = self.hwnd;
+        let delay = self.restore_delay;
 
         // 在后台线程中延迟恢复，不阻塞主线程
         std::thread::spawn(move || {
-            std::thread::sleep(std::time::Duration::from_millis(120));
+            std::thread::sleep(delay);
             unsafe {
-                if let Err(e) = PostMessageW(
-                    hwnd,
-                    WM_INPUTLANGCHANGEREQUEST,
-                    WPARAM(0),
-                    LPARAM(hkl),
-                ) {
-                    warn!("恢复输入法失败: {:?}", e);
-                } else {
-                    info!("ImeGuard: 已恢复输入法 {:#x}", hkl);
+                match restore {
+                    ImeRestore::None => {}
+                    ImeRestore::Layout { hwnd, hkl } => {
+                        if let Err(e) =
+                            PostMessageW(hwnd, WM_INPUTLANGCHANGEREQUEST, WPARAM(0), LPARAM(hkl))
+                        {
+                            warn!("恢复输入法失败: {:?}", e);
+                        } else {
+                            info!("ImeGuard: 已恢复输入法 {:#x}", hkl);
+                        }
+                    }
+                    ImeRestore::ImeStatus {
+                        ime_hwnd,
+                        open,
+                        conversion,
+                    } => {
+                        ime_control(ime_hwnd, IMC_SETOPENSTATUS, open);
+                        ime_control(ime_hwnd, IMC_SETCONVERSIONMODE, conversion);
+                        info!("ImeGuard: 已恢复输入法状态 (open={})", open);
+                    }
                 }
             }
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{png_to_dib, PasteKeystroke};
+    use windows::Win32::UI::Input::KeyboardAndMouse::{VK_CONTROL, VK_INSERT, VK_SHIFT, VK_V};
+
+    #[test]
+    fn paste_keystrokes_use_hotkey_grammar() {
+        assert_eq!(
+            PasteKeystroke::parse("^v").unwrap(),
+            PasteKeystroke::Keys {
+                modifiers: vec![VK_CONTROL],
+                key: VK_V,
+                extended: false
+            }
+        );
+        assert_eq!(
+            PasteKeystroke::parse("Ctrl+Shift+V").unwrap(),
+            PasteKeystroke::Keys {
+                modifiers: vec![VK_CONTROL, VK_SHIFT],
+                key: VK_V,
+                extended: false
+            }
+        );
+        assert_eq!(
+            PasteKeystroke::parse("Shift+Insert").unwrap(),
+            PasteKeystroke::Keys {
+                modifiers: vec![VK_SHIFT],
+                key: VK_INSERT,
+                extended: true
+            }
+        );
+        assert_eq!(PasteKeystroke::parse("RightClick").unwrap(), PasteKeystroke::RightClick);
+        assert!(PasteKeystroke::parse("Ctrl+Hyper").is_err());
+    }
+
+    #[test]
+    fn png_is_converted_to_bottom_up_bgra_dib() {
+        // 2x2：上行 红、绿，下行 蓝、半透明白
+        let rgba = [
+            255, 0, 0, 255, 0, 255, 0, 255, //
+            0, 0, 255, 255, 255, 255, 255, 128,
+        ];
+        let mut png_data = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut png_data, 2, 2);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&rgba).unwrap();
+        }
+
+        let dib = png_to_dib(&png_data).unwrap();
+        assert_eq!(dib.len(), 40 + 16);
+        assert_eq!(&dib[4..8], &2i32.to_le_bytes());
+        assert_eq!(&dib[8..12], &2i32.to_le_bytes());
+        assert_eq!(&dib[14..16], &32u16.to_le_bytes());
+        assert_eq!(
+            &dib[40..],
+            &[255, 0, 0, 255, 255, 255, 255, 128, 0, 0, 255, 255, 0, 255, 0, 255]
+        );
+    }
+}
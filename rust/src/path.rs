@@ -0,0 +1,722 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::PathStyle;
+use crate::resolver::PathResolver;
+
+/// 路径前缀映射规则（用于网络映射盘、SMB 共享等）
+///
+/// ```toml
+/// [[path_mapping]]
+/// from = '\\server\share'
+/// to = "/mnt/share"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathMapping {
+    /// Windows 侧前缀，如 `\\server\share` 或 `Z:\`
+    pub from: String,
+    /// WSL 侧前缀，如 `/mnt/share`
+    pub to: String,
+}
+
+/// 将 Windows 路径转换为 WSL 路径
+///
+/// 先规范化 Win32 命名空间前缀，再按最长前缀优先匹配映射规则，
+/// 未命中时回退到 `/mnt/<盘符>` 转换。
+pub fn convert_path_to_wsl(path_str: &str, mappings: &[PathMapping]) -> String {
+    let path = normalize_windows_path(path_str);
+
+    if let Some(mapped) = apply_mappings(&path, mappings) {
+        return mapped;
+    }
+
+    // 处理驱动器路径 "C:\path\to\file"、"D:/temp"、"E:"
+    if let Some((drive, rest)) = split_drive(&path) {
+        let rest = rest.replace('\\', "/");
+        let rest = rest.trim_start_matches('/');
+        return format!("/mnt/{}/{}", drive.to_ascii_lowercase(), rest);
+    }
+
+    String::new()
+}
+
+/// 仅应用映射规则，未命中时返回 None
+pub fn map_path_to_wsl(path_str: &str, mappings: &[PathMapping]) -> Option<String> {
+    apply_mappings(&normalize_windows_path(path_str), mappings)
+}
+
+/// 规范化 Win32 路径命名空间，统一使用反斜杠
+///
+/// - `\\?\C:\x`、`\\.\C:\x`、`\??\C:\x` → `C:\x`
+/// - `\\?\UNC\server\share`（及 `\\.\`、`\??\` 前缀）→ `\\server\share`
+///
+/// 其余设备路径（如 `\\?\Volume{...}`、`\\.\pipe\...`）保持原样。
+pub fn normalize_windows_path(path_str: &str) -> String {
+    let path = path_str.trim().trim_matches('"').replace('/', "\\");
+
+    for prefix in [r"\\?\", r"\\.\", r"\??\"] {
+        let Some(rest) = path.strip_prefix(prefix) else {
+            continue;
+        };
+
+        if let Some(unc) = rest.get(..4).filter(|head| head.eq_ignore_ascii_case(r"UNC\")) {
+            return format!(r"\\{}", &rest[unc.len()..]);
+        }
+        if split_drive(rest).is_some() {
+            return rest.to_string();
+        }
+        break;
+    }
+
+    path
+}
+
+/// 物理路径解析接口（subst 盘符、目录联接、符号链接），便于用假实现测试
+pub trait PathCanonicalizer {
+    /// 解析已存在路径的最终物理路径，失败返回 None
+    fn canonicalize(&self, path: &str) -> Option<String>;
+
+    /// 查询 subst 盘符的目标目录，如 `Z` → `C:\proj`；普通卷返回 None
+    fn subst_target(&self, drive: char) -> Option<String>;
+}
+
+/// 将 Windows 路径解析为物理路径
+///
+/// 从最近的已存在祖先目录开始解析再拼回剩余部分（图片保存前文件尚不存在），
+/// 整条路径都无法解析时展开 subst 盘符后重试；仍失败则返回规范化后的逻辑路径。
+pub fn resolve_physical_path(path_str: &str, canonicalizer: &dyn PathCanonicalizer) -> String {
+    let mut path = normalize_windows_path(path_str);
+
+    // subst 可以嵌套，限制展开次数避免循环
+    for _ in 0..8 {
+        if let Some(resolved) = canonicalize_existing_prefix(&path, canonicalizer) {
+            return resolved;
+        }
+
+        match expand_subst(&path, canonicalizer) {
+            Some(expanded) => path = expanded,
+            None => break,
+        }
+    }
+
+    path
+}
+
+fn canonicalize_existing_prefix(
+    path: &str,
+    canonicalizer: &dyn PathCanonicalizer,
+) -> Option<String> {
+    let mut base = path.to_string();
+    let mut tail: Vec<String> = Vec::new();
+
+    loop {
+        // 裸盘符 "C:" 表示该盘的当前目录，必须补成根目录再查询
+        let query = match split_drive(&base) {
+            Some((_, "")) => format!("{}\\", base),
+            _ => base.clone(),
+        };
+
+        if let Some(resolved) = canonicalizer.canonicalize(&query) {
+            let mut result = normalize_windows_path(&resolved);
+            for part in tail.iter().rev() {
+                if !result.ends_with('\\') {
+                    result.push('\\');
+                }
+                result.push_str(part);
+            }
+            return Some(result);
+        }
+
+        let trimmed = base.trim_end_matches('\\');
+        let (parent, name) = trimmed.rsplit_once('\\')?;
+
+        // UNC 路径最短到 \\server\share，不再向上查询
+        let is_unc_root = trimmed.starts_with(r"\\")
+            && trimmed[2..].split('\\').filter(|part| !part.is_empty()).count() <= 2;
+        if parent.is_empty() || is_unc_root {
+            return None;
+        }
+
+        tail.push(name.to_string());
+        base = parent.to_string();
+    }
+}
+
+fn expand_subst(path: &str, canonicalizer: &dyn PathCanonicalizer) -> Option<String> {
+    let (drive, rest) = split_drive(path)?;
+    let target = normalize_windows_path(&canonicalizer.subst_target(drive)?);
+    let rest = rest.trim_start_matches('\\');
+
+    Some(if rest.is_empty() {
+        target
+    } else {
+        format!("{}\\{}", target.trim_end_matches('\\'), rest)
+    })
+}
+
+/// 按指定风格输出 Windows 路径
+///
+/// WSL 风格交给解析链处理；无法表示的路径返回空字符串。
+pub fn format_path(path_str: &str, style: PathStyle, resolver: &dyn PathResolver) -> String {
+    match style {
+        PathStyle::Wsl => resolver
+            .to_wsl(&normalize_windows_path(path_str))
+            .unwrap_or_default(),
+        PathStyle::Windows => normalize_windows_path(path_str),
+        PathStyle::Msys => convert_path_to_posix(&normalize_windows_path(path_str), "/"),
+        PathStyle::Cygwin => convert_path_to_posix(&normalize_windows_path(path_str), "/cygdrive/"),
+        PathStyle::FileUri => convert_path_to_file_uri(&normalize_windows_path(path_str)),
+    }
+}
+
+/// 输出相对于项目根目录的路径（`./screenshots/a.png` 或 `.\screenshots\a.png`）
+///
+/// 两者均为 Windows 路径；路径不在根目录下或目标风格无法表示相对路径（file URI）时返回 None，
+/// 由调用方回退到绝对路径。
+pub fn format_relative_path(path_str: &str, root: &str, style: PathStyle) -> Option<String> {
+    let path = unify_wsl_share(&normalize_windows_path(path_str));
+    let root = unify_wsl_share(&normalize_windows_path(root));
+    let root = root.trim_end_matches('\\');
+    if root.is_empty() {
+        return None;
+    }
+
+    let head = path.get(..root.len())?;
+    if head.to_lowercase() != root.to_lowercase() {
+        return None;
+    }
+
+    let rest = &path[root.len()..];
+    if !rest.is_empty() && !rest.starts_with('\\') {
+        return None;
+    }
+    let rest = rest.trim_matches('\\');
+
+    match style {
+        PathStyle::FileUri => None,
+        PathStyle::Windows if rest.is_empty() => Some(".".to_string()),
+        PathStyle::Windows => Some(format!(".\\{}", rest)),
+        _ if rest.is_empty() => Some(".".to_string()),
+        _ => Some(format!("./{}", rest.replace('\\', "/"))),
+    }
+}
+
+/// `\\wsl$\` 与 `\\wsl.localhost\` 指向同一位置，统一为后者便于比较
+fn unify_wsl_share(path: &str) -> String {
+    match path.get(..7) {
+        Some(head) if head.eq_ignore_ascii_case(r"\\wsl$\") => {
+            format!(r"\\wsl.localhost\{}", &path[7..])
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// MSYS / Git Bash（`/c/...`）与 Cygwin（`/cygdrive/c/...`）风格，UNC 路径统一输出 `//server/share`
+fn convert_path_to_posix(path: &str, drive_root: &str) -> String {
+    if is_device_path(path) {
+        return String::new();
+    }
+
+    if path.starts_with(r"\\") {
+        return path.replace('\\', "/");
+    }
+
+    if let Some((drive, rest)) = split_drive(path) {
+        let rest = rest.replace('\\', "/");
+        let rest = rest.trim_start_matches('/');
+        return format!("{}{}/{}", drive_root, drive.to_ascii_lowercase(), rest);
+    }
+
+    String::new()
+}
+
+/// RFC 8089 文件 URI：`file:///C:/a%20b.png`，UNC 路径输出 `file://server/share/...`
+fn convert_path_to_file_uri(path: &str) -> String {
+    if is_device_path(path) {
+        return String::new();
+    }
+
+    if let Some(unc) = path.strip_prefix(r"\\") {
+        return format!("file://{}", percent_encode_path(&unc.replace('\\', "/")));
+    }
+
+    if let Some((drive, rest)) = split_drive(path) {
+        let rest = rest.replace('\\', "/");
+        return format!(
+            "file:///{}:{}",
+            drive.to_ascii_uppercase(),
+            percent_encode_path(&rest)
+        );
+    }
+
+    String::new()
+}
+
+/// 拆分 `X:` 盘符；仅接受 ASCII 字母，多字节首字符不会被误切
+fn split_drive(path: &str) -> Option<(char, &str)> {
+    let mut chars = path.chars();
+    let drive = chars.next()?;
+    if drive.is_ascii_alphabetic() && chars.next() == Some(':') {
+        Some((drive, &path[2..]))
+    } else {
+        None
+    }
+}
+
+/// 规范化后仍带命名空间前缀的设备路径（卷 GUID、管道等）
+fn is_device_path(path: &str) -> bool {
+    path.starts_with(r"\\?\") || path.starts_with(r"\\.\") || path.starts_with(r"\??\")
+}
+
+/// 对 URI 路径部分做百分号编码（保留 `/` 与 RFC 3986 unreserved 字符）
+fn percent_encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'/') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// 按最长前缀优先应用映射规则，前缀比较忽略大小写和分隔符差异
+fn apply_mappings(path_str: &str, mappings: &[PathMapping]) -> Option<String> {
+    let path = path_str.replace('/', "\\");
+
+    let mut rules: Vec<(String, &str)> = mappings
+        .iter()
+        .map(|m| (normalize_prefix(&m.from), m.to.as_str()))
+        .filter(|(from, _)| !from.is_empty())
+        .collect();
+    rules.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+
+    for (from, to) in rules {
+        let Some(head) = path.get(..from.len()) else {
+            continue;
+        };
+        if !head.eq_ignore_ascii_case(&from) {
+            continue;
+        }
+
+        // 只在路径分量边界上匹配，避免 \\server\share 命中 \\server\share2
+        let rest = &path[from.len()..];
+        if !rest.is_empty() && !rest.starts_with('\\') {
+            continue;
+        }
+
+        let rest = rest.trim_start_matches('\\').replace('\\', "/");
+        let to = to.trim_end_matches('/');
+        return Some(if rest.is_empty() {
+            if to.is_empty() {
+                "/".to_string()
+            } else {
+                to.to_string()
+            }
+        } else {
+            format!("{}/{}", to, rest)
+        });
+    }
+
+    None
+}
+
+/// 统一分隔符并去掉末尾分隔符：`Z:\` → `Z:`，`//server/share/` → `\\server\share`
+fn normalize_prefix(prefix: &str) -> String {
+    prefix
+        .trim_matches('"')
+        .replace('/', "\\")
+        .trim_end_matches('\\')
+        .to_string()
+}
+
+/// 将 WSL 路径转换回 Windows 路径（`convert_path_to_wsl` 的逆运算）
+///
+/// 依次尝试映射规则（按 WSL 侧最长前缀优先）、`/mnt/<盘符>`，
+/// 其余 Linux 原生路径转换为 `\\wsl.localhost\<distro>\...`。已是 Windows 路径时原样返回。
+pub fn convert_path_to_windows(
+    path_str: &str,
+    mappings: &[PathMapping],
+    distro: Option<&str>,
+) -> String {
+    let path_str = path_str.trim_matches(|c| c == '"' || c == '\'');
+
+    if is_windows_path(path_str) {
+        return normalize_windows_path(path_str);
+    }
+
+    if !path_str.starts_with('/') {
+        return String::new();
+    }
+
+    if let Some(mapped) = apply_reverse_mappings(path_str, mappings) {
+        return mapped;
+    }
+
+    if let Some(rest) = path_str.strip_prefix("/mnt/") {
+        let (drive, tail) = rest.split_once('/').unwrap_or((rest, ""));
+        if drive.len() == 1 && drive.as_bytes()[0].is_ascii_alphabetic() {
+            let tail = tail.trim_start_matches('/').replace('/', "\\");
+            return format!("{}:\\{}", drive.to_ascii_uppercase(), tail);
+        }
+    }
+
+    match distro {
+        Some(distro) if !distro.is_empty() => {
+            format!(r"\\wsl.localhost\{}{}", distro, path_str.replace('/', "\\"))
+        }
+        _ => String::new(),
+    }
+}
+
+/// 从剪贴板文本中拆出候选路径：每行一个，去掉首尾空白与成对引号
+pub fn split_path_list(text: &str) -> Vec<&str> {
+    text.lines()
+        .map(str::trim)
+        .map(|line| {
+            for quote in ['"', '\''] {
+                if line.len() >= 2 && line.starts_with(quote) && line.ends_with(quote) {
+                    return &line[1..line.len() - 1];
+                }
+            }
+            line
+        })
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+fn is_windows_path(path_str: &str) -> bool {
+    if path_str.starts_with(r"\\") {
+        return true;
+    }
+
+    match split_drive(path_str) {
+        Some((_, rest)) => rest.is_empty() || rest.starts_with('\\') || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// 映射规则的逆向应用：按 WSL 侧最长前缀优先，区分大小写
+fn apply_reverse_mappings(path_str: &str, mappings: &[PathMapping]) -> Option<String> {
+    let mut rules: Vec<(&str, String)> = mappings
+        .iter()
+        .map(|m| (m.to.trim_end_matches('/'), normalize_prefix(&m.from)))
+        .filter(|(to, from)| !to.is_empty() && !from.is_empty())
+        .collect();
+    rules.sort_by_key(|(to, _)| std::cmp::Reverse(to.len()));
+
+    for (to, from) in rules {
+        let Some(rest) = path_str.strip_prefix(to) else {
+            continue;
+        };
+        if !rest.is_empty() && !rest.starts_with('/') {
+            continue;
+        }
+
+        let rest = rest.trim_start_matches('/').replace('/', "\\");
+        return Some(if rest.is_empty() && !from.ends_with(':') {
+            from
+        } else {
+            format!("{}\\{}", from, rest)
+        });
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        convert_path_to_windows, convert_path_to_wsl, format_path, format_relative_path,
+        normalize_windows_path, resolve_physical_path, split_path_list, PathCanonicalizer,
+        PathMapping,
+    };
+    use crate::config::PathStyle;
+    use crate::resolver::BuiltinResolver;
+    use std::collections::HashMap;
+
+    fn styled(path: &str, style: PathStyle) -> String {
+        format_path(path, style, &BuiltinResolver)
+    }
+
+    fn mapping(from: &str, to: &str) -> PathMapping {
+        PathMapping {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    /// (输入, 规范化结果)
+    const NORMALIZE_CASES: &[(&str, &str)] = &[
+        (r"C:\Users\me\a.png", r"C:\Users\me\a.png"),
+        (r"D:/temp/x", r"D:\temp\x"),
+        (r#""C:\quoted path""#, r"C:\quoted path"),
+        (r"\\?\C:\very\long\path.png", r"C:\very\long\path.png"),
+        (r"\\.\D:\dev", r"D:\dev"),
+        (r"\??\E:\nt", r"E:\nt"),
+        (r"//?/c:/fwd", r"c:\fwd"),
+        (r"\\?\UNC\server\share\x", r"\\server\share\x"),
+        (r"\\?\unc\server\share", r"\\server\share"),
+        (r"\\.\UNC\server\share\x", r"\\server\share\x"),
+        (r"\??\UNC\server\share\x", r"\\server\share\x"),
+        (r"\\server\share\x", r"\\server\share\x"),
+        (r"\\?\Volume{0a1b}\x", r"\\?\Volume{0a1b}\x"),
+        (r"\\.\pipe\foo", r"\\.\pipe\foo"),
+        (r"\\?\", r"\\?\"),
+    ];
+
+    /// (输入, WSL 路径)；无法转换时为空字符串
+    const WSL_CASES: &[(&str, &str)] = &[
+        (r"C:\Users\me\a.png", "/mnt/c/Users/me/a.png"),
+        (r"C:\", "/mnt/c/"),
+        (r"C:", "/mnt/c/"),
+        (r"D:/temp", "/mnt/d/temp"),
+        (r"\\?\C:\very\long\path.png", "/mnt/c/very/long/path.png"),
+        (r"\\?\c:\x", "/mnt/c/x"),
+        (r"\\.\D:\dev", "/mnt/d/dev"),
+        (r"\??\E:\nt", "/mnt/e/nt"),
+        (r"\\?\UNC\server\share\x", ""),
+        (r"\\server\share\a.txt", ""),
+        (r"\\?\Volume{0a1b}\x", ""),
+        (r"\\.\pipe\foo", ""),
+        (r"图:\x", ""),
+        (r"é:\x", ""),
+        (r"1:\x", ""),
+        ("中文", ""),
+        ("C", ""),
+        ("", ""),
+    ];
+
+    #[test]
+    fn normalize_handles_win32_namespaces() {
+        for (input, expected) in NORMALIZE_CASES {
+            assert_eq!(normalize_windows_path(input), *expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn convert_path_to_wsl_table() {
+        for (input, expected) in WSL_CASES {
+            assert_eq!(convert_path_to_wsl(input, &[]), *expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    fn namespaced_paths_use_mappings_and_other_styles() {
+        let rules = [mapping(r"\\server\share", "/mnt/share")];
+        let unc = r"\\?\UNC\server\share\x y.png";
+
+        assert_eq!(convert_path_to_wsl(unc, &rules), "/mnt/share/x y.png");
+        assert_eq!(styled(unc, PathStyle::Windows), r"\\server\share\x y.png");
+        assert_eq!(styled(unc, PathStyle::Msys), "//server/share/x y.png");
+        assert_eq!(styled(unc, PathStyle::FileUri), "file://server/share/x%20y.png");
+        assert_eq!(styled(r"\\?\C:\a", PathStyle::Cygwin), "/cygdrive/c/a");
+
+        for style in [PathStyle::Wsl, PathStyle::Msys, PathStyle::Cygwin, PathStyle::FileUri] {
+            assert_eq!(styled(r"\\?\Volume{0a1b}\x", style), "", "{style:?}");
+        }
+    }
+
+    #[test]
+    fn mappings_rewrite_unc_and_mapped_drives() {
+        let rules = [
+            mapping(r"\\server\share", "/mnt/share"),
+            mapping(r"Z:\", "/net/proj"),
+        ];
+
+        assert_eq!(
+            convert_path_to_wsl(r"\\SERVER\Share\docs\a.txt", &rules),
+            "/mnt/share/docs/a.txt"
+        );
+        assert_eq!(convert_path_to_wsl(r"\\server\share", &rules), "/mnt/share");
+        assert_eq!(convert_path_to_wsl(r"z:\src\main.rs", &rules), "/net/proj/src/main.rs");
+        assert_eq!(convert_path_to_wsl(r"C:\x", &rules), "/mnt/c/x");
+    }
+
+    #[test]
+    fn mappings_prefer_longest_prefix_on_component_boundary() {
+        let rules = [
+            mapping(r"\\server\share", "/mnt/share"),
+            mapping(r"\\server\share\deep", "/deep/"),
+            mapping(r"\\server\share2", "/mnt/other"),
+        ];
+
+        assert_eq!(convert_path_to_wsl(r"\\server\share\deep\x", &rules), "/deep/x");
+        assert_eq!(convert_path_to_wsl(r"\\server\share\deeper", &rules), "/mnt/share/deeper");
+        assert_eq!(convert_path_to_wsl(r"\\server\share2\y", &rules), "/mnt/other/y");
+    }
+
+    #[test]
+    fn format_path_supports_every_style() {
+        let path = r"C:\Users\me\my shot.png";
+
+        assert_eq!(styled(path, PathStyle::Wsl), "/mnt/c/Users/me/my shot.png");
+        assert_eq!(styled(path, PathStyle::Windows), path);
+        assert_eq!(styled(path, PathStyle::Msys), "/c/Users/me/my shot.png");
+        assert_eq!(
+            styled(path, PathStyle::Cygwin),
+            "/cygdrive/c/Users/me/my shot.png"
+        );
+        assert_eq!(
+            styled(path, PathStyle::FileUri),
+            "file:///C:/Users/me/my%20shot.png"
+        );
+    }
+
+    #[test]
+    fn format_path_handles_unc_and_non_ascii() {
+        let unc = r"\\server\share\图 1.png";
+
+        assert_eq!(styled(unc, PathStyle::Msys), "//server/share/图 1.png");
+        assert_eq!(styled(unc, PathStyle::Cygwin), "//server/share/图 1.png");
+        assert_eq!(
+            styled(unc, PathStyle::FileUri),
+            "file://server/share/%E5%9B%BE%201.png"
+        );
+        assert_eq!(styled("d:/x", PathStyle::Windows), r"d:\x");
+    }
+
+    #[test]
+    fn reverse_conversion_inverts_wsl_paths() {
+        let rules = [
+            mapping(r"\\server\share", "/mnt/share"),
+            mapping(r"Z:\", "/net/proj"),
+        ];
+        let distro = Some("Ubuntu-22.04");
+
+        assert_eq!(
+            convert_path_to_windows("/mnt/c/Users/me/out.png", &rules, distro),
+            r"C:\Users\me\out.png"
+        );
+        assert_eq!(convert_path_to_windows("/mnt/d", &rules, distro), r"D:\");
+        assert_eq!(
+            convert_path_to_windows("/mnt/share/docs/a.txt", &rules, distro),
+            r"\\server\share\docs\a.txt"
+        );
+        assert_eq!(convert_path_to_windows("/mnt/share", &rules, distro), r"\\server\share");
+        assert_eq!(convert_path_to_windows("/net/proj", &rules, distro), r"Z:\");
+        assert_eq!(
+            convert_path_to_windows("/home/me/diagram.png", &rules, distro),
+            r"\\wsl.localhost\Ubuntu-22.04\home\me\diagram.png"
+        );
+        assert_eq!(convert_path_to_windows("/home/me/x", &rules, None), "");
+        assert_eq!(convert_path_to_windows("relative/x", &rules, distro), "");
+        assert_eq!(convert_path_to_windows(r"C:\x", &rules, distro), r"C:\x");
+    }
+
+    #[test]
+    fn reverse_conversion_round_trips() {
+        let rules = [mapping(r"\\server\share", "/mnt/share")];
+        for path in [r"C:\Users\me\a.png", r"\\server\share\x\y.txt"] {
+            let wsl = convert_path_to_wsl(path, &rules);
+            assert_eq!(convert_path_to_windows(&wsl, &rules, None), path);
+        }
+    }
+
+    #[test]
+    fn split_path_list_strips_quotes_and_blank_lines() {
+        let text = "  '/mnt/c/a b.png'\n\n\"/home/me/x\"\r\n/tmp/y  \n";
+        assert_eq!(split_path_list(text), vec!["/mnt/c/a b.png", "/home/me/x", "/tmp/y"]);
+    }
+
+    /// 假的物理路径解析器：`existing` 以小写路径为键，`subst` 为盘符映射
+    #[derive(Default)]
+    struct FakeCanonicalizer {
+        existing: HashMap<String, String>,
+        subst: HashMap<char, String>,
+    }
+
+    impl FakeCanonicalizer {
+        fn exists(mut self, path: &str, physical: &str) -> Self {
+            self.existing.insert(path.to_lowercase(), physical.to_string());
+            self
+        }
+
+        fn subst(mut self, drive: char, target: &str) -> Self {
+            self.subst.insert(drive.to_ascii_uppercase(), target.to_string());
+            self
+        }
+    }
+
+    impl PathCanonicalizer for FakeCanonicalizer {
+        fn canonicalize(&self, path: &str) -> Option<String> {
+            self.existing.get(&path.to_lowercase()).cloned()
+        }
+
+        fn subst_target(&self, drive: char) -> Option<String> {
+            self.subst.get(&drive.to_ascii_uppercase()).cloned()
+        }
+    }
+
+    #[test]
+    fn physical_resolution_follows_junctions_for_missing_files() {
+        let fake = FakeCanonicalizer::default()
+            .exists(r"C:\Users\me\work", r"\\?\D:\real\work")
+            .exists(r"C:\", r"\\?\C:\");
+
+        assert_eq!(
+            resolve_physical_path(r"C:\Users\me\work\shots\clip.png", &fake),
+            r"D:\real\work\shots\clip.png"
+        );
+        assert_eq!(resolve_physical_path(r"C:\other\x.png", &fake), r"C:\other\x.png");
+    }
+
+    #[test]
+    fn physical_resolution_expands_nested_subst_drives() {
+        let fake = FakeCanonicalizer::default()
+            .subst('z', r"\??\Y:\inner")
+            .subst('y', r"C:\proj");
+
+        assert_eq!(resolve_physical_path(r"Z:\src\main.rs", &fake), r"C:\proj\inner\src\main.rs");
+        assert_eq!(resolve_physical_path(r"Z:", &fake), r"C:\proj\inner");
+        assert_eq!(
+            convert_path_to_wsl(&resolve_physical_path(r"Z:\a", &fake), &[]),
+            "/mnt/c/proj/inner/a"
+        );
+    }
+
+    #[test]
+    fn physical_resolution_keeps_unresolvable_paths() {
+        let fake = FakeCanonicalizer::default().subst('a', r"B:\").subst('b', r"A:\");
+
+        assert_eq!(resolve_physical_path(r"\\server\share\x", &fake), r"\\server\share\x");
+        assert_eq!(resolve_physical_path(r"Q:\x", &fake), r"Q:\x");
+        // 互相引用的 subst 不会死循环
+        assert!(!resolve_physical_path(r"A:\x", &fake).is_empty());
+    }
+
+    #[test]
+    fn relative_paths_inside_project_root() {
+        let root = r"D:\work\repo\";
+        let shot = r"d:\Work\repo\screenshots\foo.png";
+
+        assert_eq!(
+            format_relative_path(shot, root, PathStyle::Wsl).as_deref(),
+            Some("./screenshots/foo.png")
+        );
+        assert_eq!(
+            format_relative_path(shot, root, PathStyle::Windows).as_deref(),
+            Some(r".\screenshots\foo.png")
+        );
+        assert_eq!(format_relative_path(root, root, PathStyle::Msys).as_deref(), Some("."));
+        assert_eq!(
+            format_relative_path(
+                r"\\wsl.localhost\Ubuntu\home\me\repo\a.png",
+                r"\\wsl$\Ubuntu\home\me\repo",
+                PathStyle::Wsl,
+            )
+            .as_deref(),
+            Some("./a.png")
+        );
+    }
+
+    #[test]
+    fn relative_paths_fall_back_outside_root() {
+        let root = r"D:\work\repo";
+
+        assert_eq!(format_relative_path(r"D:\work\repo2\a.png", root, PathStyle::Wsl), None);
+        assert_eq!(format_relative_path(r"C:\temp\a.png", root, PathStyle::Wsl), None);
+        assert_eq!(format_relative_path(r"D:\work\repo\a.png", root, PathStyle::FileUri), None);
+        assert_eq!(format_relative_path(r"D:\work\repo\a.png", "", PathStyle::Wsl), None);
+    }
+}
@@ -0,0 +1,317 @@
+use anyhow::Context;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tracing::{info, warn};
+
+use crate::config::{AppConfig, PasteFormat, PasteMethod, PathStyle, QuoteStyle, RuntimeMode};
+use crate::paste::PasteKeystrokeSpec;
+
+/// 按前台窗口匹配的应用配置（`[[profile]]`）
+///
+/// ```toml
+/// [[profile]]
+/// name = "Windows Terminal"
+/// process = "WindowsTerminal.exe"
+/// paste_keystroke = "Ctrl+Shift+V"
+///
+/// [[profile]]
+/// title = "(?i)my-app"
+/// project_root = "/home/me/my-app"
+/// relative_paths = true
+///
+/// [[profile]]
+/// title = "(?i)chatgpt|claude"
+/// window_class = "Chrome_WidgetWin_1"
+/// paste_format = "attachment"
+/// ```
+///
+/// 匹配条件之间为“与”关系，未填写的条件不参与匹配；按顺序取第一个匹配的 profile。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    /// 日志中显示的名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(flatten)]
+    pub rule: WindowRule,
+
+    #[serde(flatten)]
+    pub overrides: ConfigOverrides,
+}
+
+/// 窗口匹配条件，用于 `[[profile]]` 与 `[target]`
+///
+/// 条件之间为“与”关系，未填写的条件不参与匹配；没有任何条件的规则不匹配任何窗口。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WindowRule {
+    /// 进程名（不区分大小写，`.exe` 可省略）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<String>,
+
+    /// 窗口类名（不区分大小写）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_class: Option<String>,
+
+    /// 窗口标题正则
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<TitlePattern>,
+}
+
+/// 窗口标题正则，读取配置时编译一次
+///
+/// 无效的正则不会导致整个配置读取失败：该规则不匹配任何窗口，由 `validate` 报告错误。
+#[derive(Debug, Clone)]
+pub struct TitlePattern {
+    pattern: String,
+    regex: Result<Regex, regex::Error>,
+}
+
+/// 可被 `[[profile]]` 与 `[[action]]` 覆盖的配置项，未设置的项保持原值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfigOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime_mode: Option<RuntimeMode>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paste_format: Option<PasteFormat>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paste_method: Option<PasteMethod>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_style: Option<PathStyle>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paste_keystroke: Option<PasteKeystrokeSpec>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_style: Option<QuoteStyle>,
+
+    /// 项目根目录（Windows 或 WSL 路径）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_root: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relative_paths: Option<bool>,
+}
+
+/// 前台窗口信息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WindowInfo {
+    /// 进程可执行文件名，如 `WindowsTerminal.exe`
+    pub process_name: String,
+    pub class_name: String,
+    pub title: String,
+}
+
+impl TitlePattern {
+    pub fn new(pattern: impl Into<String>) -> Self {
+        let pattern = pattern.into();
+        let regex = Regex::new(&pattern);
+        if let Err(e) = &regex {
+            warn!("窗口标题正则无效 {}: {}", pattern, e);
+        }
+        Self { pattern, regex }
+    }
+
+    fn is_match(&self, title: &str) -> bool {
+        self.regex.as_ref().is_ok_and(|regex| regex.is_match(title))
+    }
+}
+
+impl Serialize for TitlePattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.pattern)
+    }
+}
+
+impl<'de> Deserialize<'de> for TitlePattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+impl WindowRule {
+    /// 检查标题正则能否编译
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(title) = &self.title {
+            if let Err(e) = &title.regex {
+                return Err(e.clone()).with_context(|| format!("窗口标题正则无效: {}", title.pattern));
+            }
+        }
+        Ok(())
+    }
+
+    /// 判断规则是否匹配窗口
+    pub fn matches(&self, window: &WindowInfo) -> bool {
+        if self.process.is_none() && self.window_class.is_none() && self.title.is_none() {
+            return false;
+        }
+
+        let process_matches = self.process.as_deref().is_none_or(|process| {
+            strip_exe(process).eq_ignore_ascii_case(strip_exe(&window.process_name))
+        });
+        let class_matches = self
+            .window_class
+            .as_deref()
+            .is_none_or(|class| class.eq_ignore_ascii_case(&window.class_name));
+        let title_matches = self
+            .title
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(&window.title));
+
+        process_matches && class_matches && title_matches
+    }
+}
+
+impl ConfigOverrides {
+    /// 将设置的项覆盖到配置上
+    pub fn apply_to(&self, config: &mut AppConfig) {
+        if let Some(mode) = &self.runtime_mode {
+            config.runtime_mode = mode.clone();
+        }
+        if let Some(format) = &self.paste_format {
+            config.paste_format = format.clone();
+        }
+        if let Some(method) = self.paste_method {
+            config.paste_method = method;
+        }
+        if let Some(style) = self.path_style {
+            config.path_style = style;
+        }
+        if let Some(keystroke) = &self.paste_keystroke {
+            config.paste_keystroke = keystroke.clone();
+        }
+        if let Some(style) = self.quote_style {
+            config.quote_style = style;
+        }
+        if let Some(root) = &self.project_root {
+            config.project_root = Some(root.clone());
+        }
+        if let Some(relative) = self.relative_paths {
+            config.relative_paths = relative;
+        }
+    }
+}
+
+/// 按前台窗口选出第一个匹配的 profile，返回覆盖后的配置
+pub fn resolve_config(config: &AppConfig, window: Option<&WindowInfo>) -> AppConfig {
+    let mut resolved = config.clone();
+
+    let Some(window) = window else {
+        return resolved;
+    };
+
+    if let Some(profile) = config.profiles.iter().find(|profile| profile.rule.matches(window)) {
+        info!(
+            "应用 profile: {} ({})",
+            profile.name.as_deref().unwrap_or("未命名"),
+            window.process_name
+        );
+        profile.overrides.apply_to(&mut resolved);
+    }
+
+    resolved
+}
+
+fn strip_exe(name: &str) -> &str {
+    let len = name.len();
+    if len > 4 && name[len - 4..].eq_ignore_ascii_case(".exe") {
+        &name[..len - 4]
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve_config, TitlePattern, WindowInfo, WindowRule};
+    use crate::config::{AppConfig, PathStyle, QuoteStyle};
+
+    fn window(process: &str, class: &str, title: &str) -> WindowInfo {
+        WindowInfo {
+            process_name: process.to_string(),
+            class_name: class.to_string(),
+            title: title.to_string(),
+        }
+    }
+
+    #[test]
+    fn all_configured_conditions_must_match() {
+        let rule = WindowRule {
+            process: Some("windowsterminal".to_string()),
+            title: Some(TitlePattern::new("^PowerShell")),
+            ..WindowRule::default()
+        };
+
+        assert!(rule.matches(&window("WindowsTerminal.exe", "CASCADIA", "PowerShell 7")));
+        assert!(!rule.matches(&window("WindowsTerminal.exe", "CASCADIA", "Ubuntu")));
+        assert!(!rule.matches(&window("pwsh.exe", "ConsoleWindowClass", "PowerShell 7")));
+        assert!(!WindowRule::default().matches(&window("any.exe", "Any", "Any")));
+
+        let invalid = WindowRule {
+            title: Some(TitlePattern::new("(")),
+            ..WindowRule::default()
+        };
+        assert!(!invalid.matches(&window("a.exe", "A", "(")));
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn first_matching_profile_overrides_config() {
+        let config: AppConfig = toml::from_str(
+            r#"
+            hotkey = "!v"
+            runtime_mode = "fast"
+            paste_format = "plain"
+
+            [[profile]]
+            name = "PowerShell"
+            window_class = "consolewindowclass"
+            path_style = "windows"
+            quote_style = "powershell"
+
+            [[profile]]
+            process = "conhost.exe"
+            paste_keystroke = "RightClick"
+            "#,
+        )
+        .unwrap();
+
+        let console = window("conhost.exe", "ConsoleWindowClass", "Windows PowerShell");
+        let resolved = resolve_config(&config, Some(&console));
+        assert_eq!(resolved.path_style, PathStyle::Windows);
+        assert_eq!(resolved.quote_style, QuoteStyle::PowerShell);
+        assert_eq!(resolved.paste_keystroke.as_str(), "^v");
+
+        let other = resolve_config(&config, Some(&window("mintty.exe", "mintty", "~")));
+        assert_eq!(other.path_style, PathStyle::Wsl);
+        assert_eq!(resolve_config(&config, None).path_style, PathStyle::Wsl);
+    }
+
+    #[test]
+    fn profile_sets_project_root() {
+        let config: AppConfig = toml::from_str(
+            r#"
+            hotkey = "!v"
+            runtime_mode = "fast"
+            paste_format = "plain"
+            project_root = "C:\\work"
+
+            [[profile]]
+            title = "(?i)my-app"
+            project_root = "/home/me/my-app"
+            relative_paths = true
+            "#,
+        )
+        .unwrap();
+
+        let resolved = resolve_config(&config, Some(&window("WindowsTerminal.exe", "CASCADIA", "My-App: zsh")));
+        assert_eq!(resolved.project_root.as_deref(), Some("/home/me/my-app"));
+        assert!(resolved.relative_paths);
+
+        let other = resolve_config(&config, Some(&window("WindowsTerminal.exe", "CASCADIA", "Ubuntu")));
+        assert_eq!(other.project_root.as_deref(), Some("C:\\work"));
+        assert!(!other.relative_paths);
+    }
+}
@@ -0,0 +1,141 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 从终端窗口标题中提取工作目录
+///
+/// 支持 `user@host: /mnt/d/work/repo`、`PowerShell - C:\work\repo` 等常见格式，
+/// 路径到标题结尾或 ` - `、` | ` 等分隔符为止。
+pub fn path_from_window_title(title: &str) -> Option<String> {
+    let chars: Vec<(usize, char)> = title.char_indices().collect();
+
+    for (index, &(offset, ch)) in chars.iter().enumerate() {
+        let at_boundary = index == 0 || matches!(chars[index - 1].1, ' ' | ':' | '[' | '(');
+        if !at_boundary {
+            continue;
+        }
+
+        let is_posix = ch == '/';
+        let is_drive = ch.is_ascii_alphabetic()
+            && chars.get(index + 1).map(|c| c.1) == Some(':')
+            && matches!(chars.get(index + 2).map(|c| c.1), Some('\\') | Some('/'));
+
+        if is_posix || is_drive {
+            let candidate = cut_title_suffix(&title[offset..]);
+            if !candidate.is_empty() {
+                return Some(candidate.to_string());
+            }
+        }
+    }
+
+    None
+}
+
+fn cut_title_suffix(text: &str) -> &str {
+    let end = [" - ", " — ", " | ", "]", ")"]
+        .iter()
+        .filter_map(|separator| text.find(separator))
+        .min()
+        .unwrap_or(text.len());
+    text[..end].trim()
+}
+
+/// 自下而上查找包含 `.git`（目录或 worktree 的 gitdir 文件）的仓库根目录
+pub fn find_git_root(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// 确保附件目录写入 `.git/info/exclude`，避免截图被提交
+pub fn ensure_git_exclude(repo_root: &Path, attachment_dir: &str) -> Result<()> {
+    let Some(git_dir) = resolve_git_dir(repo_root) else {
+        return Ok(());
+    };
+
+    let pattern = format!("/{}/", attachment_dir.trim_matches(|c| c == '/' || c == '\\'));
+    let exclude_path = git_dir.join("info").join("exclude");
+
+    let existing = fs::read_to_string(&exclude_path).unwrap_or_default();
+    if existing.lines().any(|line| line.trim() == pattern) {
+        return Ok(());
+    }
+
+    fs::create_dir_all(git_dir.join("info")).context("创建 .git/info 目录失败")?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&exclude_path)
+        .context("打开 .git/info/exclude 失败")?;
+
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        writeln!(file)?;
+    }
+    writeln!(file, "{}", pattern).context("写入 .git/info/exclude 失败")?;
+
+    Ok(())
+}
+
+/// `.git` 为目录时直接使用；为文件时（worktree / submodule）读取 `gitdir:` 指向，
+/// worktree 的 info/exclude 位于 commondir 中
+fn resolve_git_dir(repo_root: &Path) -> Option<PathBuf> {
+    let dot_git = repo_root.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+
+    let content = fs::read_to_string(&dot_git).ok()?;
+    let git_dir = content.lines().find_map(|line| line.strip_prefix("gitdir:"))?.trim();
+    let git_dir = repo_root.join(git_dir);
+
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common) => Some(git_dir.join(common.trim())),
+        Err(_) => Some(git_dir),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ensure_git_exclude, find_git_root, path_from_window_title};
+
+    #[test]
+    fn extracts_paths_from_terminal_titles() {
+        let cases = [
+            ("me@host: /mnt/d/work/repo", Some("/mnt/d/work/repo")),
+            ("me@host:/home/me/repo", Some("/home/me/repo")),
+            ("Administrator: PowerShell - C:\\work\\repo", Some("C:\\work\\repo")),
+            ("PS C:\\work\\repo - Windows Terminal", Some("C:\\work\\repo")),
+            ("/mnt/c/Users/me/项目 - zsh", Some("/mnt/c/Users/me/项目")),
+            ("[/home/me/repo] vim", Some("/home/me/repo")),
+            ("repo - Visual Studio Code", None),
+            ("", None),
+        ];
+
+        for (title, expected) in cases {
+            assert_eq!(path_from_window_title(title).as_deref(), expected, "title: {title}");
+        }
+    }
+
+    #[test]
+    fn git_exclude_is_added_once() {
+        let root = std::env::temp_dir().join(format!(
+            "wsl_clipboard_project_test_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("src").join("deep")).unwrap();
+
+        assert_eq!(find_git_root(&root.join("src").join("deep")), Some(root.clone()));
+
+        ensure_git_exclude(&root, ".ai-attachments").unwrap();
+        ensure_git_exclude(&root, ".ai-attachments/").unwrap();
+
+        let exclude = std::fs::read_to_string(root.join(".git/info/exclude")).unwrap();
+        assert_eq!(exclude, "/.ai-attachments/\n");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
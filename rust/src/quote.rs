@@ -0,0 +1,308 @@
+use crate::config::{PathSeparator, QuoteStyle};
+
+/// 按 shell 规则引用单个路径
+///
+/// 只含安全字符的路径保持原样，其余情况按目标 shell 的单引号规则包裹。
+pub fn quote_path(path: &str, style: QuoteStyle) -> String {
+    let is_safe = match style {
+        QuoteStyle::None => return path.to_string(),
+        QuoteStyle::Posix | QuoteStyle::Fish => is_posix_safe,
+        QuoteStyle::PowerShell => is_powershell_safe,
+    };
+    if is_plain_word(path, is_safe) {
+        return path.to_string();
+    }
+    force_quote(path, style)
+}
+
+/// 按分隔符的要求引用单个路径：`nul` 分隔时每个路径都加引号
+pub fn quote_path_for(path: &str, style: QuoteStyle, separator: PathSeparator) -> String {
+    match separator {
+        PathSeparator::Nul => force_quote(path, style),
+        PathSeparator::Space | PathSeparator::Newline => quote_path(path, style),
+    }
+}
+
+/// 无条件按目标 shell 的单引号规则包裹（`none` 按 POSIX 处理）
+fn force_quote(path: &str, style: QuoteStyle) -> String {
+    match style {
+        QuoteStyle::Posix | QuoteStyle::None => {
+            // 单引号内无任何转义，' 需要先闭合再以 \' 输出
+            format!("'{}'", path.replace('\'', r"'\''"))
+        }
+        QuoteStyle::Fish => {
+            // fish 单引号内只有 \\ 和 \' 两种转义
+            let mut quoted = String::with_capacity(path.len() + 2);
+            quoted.push('\'');
+            for ch in path.chars() {
+                if ch == '\\' || ch == '\'' {
+                    quoted.push('\\');
+                }
+                quoted.push(ch);
+            }
+            quoted.push('\'');
+            quoted
+        }
+        QuoteStyle::PowerShell => {
+            // PowerShell 把弯引号 ‘ ’ ‚ ‛ 也视作单引号，需要同样双写
+            let mut quoted = String::with_capacity(path.len() + 2);
+            quoted.push('\'');
+            for ch in path.chars() {
+                if is_powershell_single_quote(ch) {
+                    quoted.push(ch);
+                }
+                quoted.push(ch);
+            }
+            quoted.push('\'');
+            quoted
+        }
+    }
+}
+
+/// 引用并拼接多个路径
+pub fn join_paths<S: AsRef<str>>(
+    paths: &[S],
+    style: QuoteStyle,
+    separator: PathSeparator,
+) -> String {
+    paths
+        .iter()
+        .map(|path| quote_path_for(path.as_ref(), style, separator))
+        .collect::<Vec<_>>()
+        .join(separator.as_str())
+}
+
+impl PathSeparator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PathSeparator::Space => " ",
+            PathSeparator::Newline => "\n",
+            // NUL 经剪贴板或键入都无法送达，改为空格分隔并给每个路径加引号
+            PathSeparator::Nul => " ",
+        }
+    }
+}
+
+fn is_plain_word(path: &str, is_safe: fn(char) -> bool) -> bool {
+    !path.is_empty() && !path.starts_with('-') && path.chars().all(is_safe)
+}
+
+/// bash / zsh / fish 中无需引用的字符（不含 `=`，避免 zsh 的 `=cmd` 展开）
+fn is_posix_safe(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || matches!(ch, '_' | '@' | '%' | '+' | ':' | ',' | '.' | '/' | '-')
+}
+
+fn is_powershell_safe(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || matches!(ch, '_' | ':' | '.' | '/' | '\\' | '-')
+}
+
+fn is_powershell_single_quote(ch: char) -> bool {
+    matches!(ch, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_powershell_single_quote, join_paths, quote_path};
+    use crate::config::{AppConfig, PathSeparator, QuoteStyle};
+    use crate::typing::{key_events, KeyEvent};
+    use proptest::prelude::*;
+
+    /// fish 单引号语法的最小解析器：空白分词，支持 '...' 内的 \\ 与 \'
+    fn split_fish(input: &str) -> Vec<String> {
+        split_words(input, |chars, word| {
+            while let Some(ch) = chars.next() {
+                match ch {
+                    '\'' => return,
+                    '\\' => match chars.peek() {
+                        Some('\\') | Some('\'') => word.push(chars.next().unwrap()),
+                        _ => word.push('\\'),
+                    },
+                    _ => word.push(ch),
+                }
+            }
+            panic!("未闭合的单引号: {input}");
+        })
+    }
+
+    /// PowerShell 单引号语法的最小解析器：引号字符双写表示字面量
+    fn split_powershell(input: &str) -> Vec<String> {
+        split_words(input, |chars, word| {
+            while let Some(ch) = chars.next() {
+                if is_powershell_single_quote(ch) {
+                    match chars.peek() {
+                        Some(&next) if is_powershell_single_quote(next) => {
+                            word.push(chars.next().unwrap());
+                        }
+                        _ => return,
+                    }
+                } else {
+                    word.push(ch);
+                }
+            }
+            panic!("未闭合的单引号: {input}");
+        })
+    }
+
+    fn split_words(
+        input: &str,
+        read_quoted: impl Fn(&mut std::iter::Peekable<std::str::Chars<'_>>, &mut String),
+    ) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut chars = input.chars().peekable();
+        while let Some(&ch) = chars.peek() {
+            if ch == ' ' || ch == '\n' {
+                chars.next();
+                continue;
+            }
+            let mut word = String::new();
+            while let Some(&ch) = chars.peek() {
+                if ch == ' ' || ch == '\n' {
+                    break;
+                }
+                chars.next();
+                if ch == '\'' {
+                    read_quoted(&mut chars, &mut word);
+                } else {
+                    word.push(ch);
+                }
+            }
+            words.push(word);
+        }
+        words
+    }
+
+    fn path_strategy() -> impl Strategy<Value = String> {
+        // 覆盖空格、括号、$、引号、反斜杠、CJK 标点和弯引号
+        proptest::collection::vec(
+            prop_oneof![
+                proptest::char::range('a', 'z'),
+                Just(' '),
+                Just('('),
+                Just(')'),
+                Just('$'),
+                Just('\''),
+                Just('"'),
+                Just('\\'),
+                Just('/'),
+                Just('`'),
+                Just('!'),
+                Just('='),
+                Just('-'),
+                Just('，'),
+                Just('（'),
+                Just('’'),
+                Just('图'),
+            ],
+            1..24,
+        )
+        .prop_map(|chars| chars.into_iter().collect())
+    }
+
+    #[test]
+    fn safe_paths_stay_unquoted() {
+        for style in [QuoteStyle::Posix, QuoteStyle::Fish, QuoteStyle::PowerShell] {
+            assert_eq!(quote_path("/mnt/c/temp/clip_1.png", style), "/mnt/c/temp/clip_1.png");
+        }
+        assert_eq!(quote_path("it's here", QuoteStyle::None), "it's here");
+    }
+
+    #[test]
+    fn special_characters_are_quoted() {
+        assert_eq!(quote_path("/mnt/c/a b(1).png", QuoteStyle::Posix), "'/mnt/c/a b(1).png'");
+        assert_eq!(quote_path("/mnt/c/it's", QuoteStyle::Posix), r"'/mnt/c/it'\''s'");
+        assert_eq!(quote_path(r"C:\it's\x", QuoteStyle::Fish), r"'C:\\it\'s\\x'");
+        assert_eq!(quote_path(r"C:\it’s $x", QuoteStyle::PowerShell), r"'C:\it’’s $x'");
+        assert_eq!(quote_path("/mnt/c/截图，1.png", QuoteStyle::Posix), "'/mnt/c/截图，1.png'");
+    }
+
+    #[test]
+    fn join_uses_configured_separator() {
+        let paths = ["/mnt/c/a.png", "/mnt/c/b c.png"];
+        assert_eq!(
+            join_paths(&paths, QuoteStyle::Posix, PathSeparator::Space),
+            "/mnt/c/a.png '/mnt/c/b c.png'"
+        );
+        assert_eq!(
+            join_paths(&paths, QuoteStyle::Posix, PathSeparator::Newline),
+            "/mnt/c/a.png\n'/mnt/c/b c.png'"
+        );
+        assert_eq!(
+            join_paths(&paths, QuoteStyle::Posix, PathSeparator::Nul),
+            "'/mnt/c/a.png' '/mnt/c/b c.png'"
+        );
+        assert_eq!(
+            join_paths(&paths, QuoteStyle::None, PathSeparator::Nul),
+            "'/mnt/c/a.png' '/mnt/c/b c.png'"
+        );
+    }
+
+    #[test]
+    fn reverse_output_is_unquoted_by_default() {
+        let config: AppConfig = toml::from_str("hotkey = \"!v\"\nruntime_mode = \"safe\"\npaste_format = \"plain\"").unwrap();
+        let paths = [r"C:\Users\me\out.png", r"\\wsl.localhost\Ubuntu\home\me\a.png"];
+
+        assert_eq!(config.quote_style, QuoteStyle::Posix);
+        assert_eq!(
+            join_paths(&paths, config.reverse_quote_style, config.path_separator),
+            r"C:\Users\me\out.png \\wsl.localhost\Ubuntu\home\me\a.png"
+        );
+    }
+
+    /// CF_UNICODETEXT 在第一个 NUL 处截断
+    fn deliver_clipboard(text: &str) -> String {
+        text.split('\0').next().unwrap_or_default().to_string()
+    }
+
+    /// 按模拟键入的按键事件还原目标窗口收到的文本
+    fn deliver_typed(text: &str) -> String {
+        let units: Vec<u16> = key_events(text)
+            .concat()
+            .into_iter()
+            .filter_map(|event| match event {
+                KeyEvent::Unicode { unit, key_up: false } => Some(unit),
+                KeyEvent::VirtualKey { vk: 0x09, key_up: false } => Some(u16::from(b'\t')),
+                _ => None,
+            })
+            .collect();
+        String::from_utf16(&units).unwrap()
+    }
+
+    #[test]
+    fn separators_survive_clipboard_and_typing() {
+        let paths = ["/mnt/c/a.png", "/mnt/c/b c.png", "/mnt/c/it's.png"];
+        let joined = join_paths(&paths, QuoteStyle::Posix, PathSeparator::Nul);
+        assert!(!joined.chars().any(char::is_control));
+        for separator in [PathSeparator::Space, PathSeparator::Newline, PathSeparator::Nul] {
+            let joined = join_paths(&paths, QuoteStyle::Posix, separator);
+            for delivered in [deliver_clipboard(&joined), deliver_typed(&joined)] {
+                assert_eq!(shell_words::split(&delivered).unwrap(), paths, "{separator:?}");
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn posix_round_trips_through_shell_words(paths in proptest::collection::vec(path_strategy(), 1..4)) {
+            for separator in [PathSeparator::Space, PathSeparator::Newline, PathSeparator::Nul] {
+                let joined = join_paths(&paths, QuoteStyle::Posix, separator);
+                prop_assert_eq!(shell_words::split(&joined).unwrap(), paths.clone());
+            }
+            let joined = join_paths(&paths, QuoteStyle::None, PathSeparator::Nul);
+            prop_assert_eq!(shell_words::split(&joined).unwrap(), paths);
+        }
+
+        #[test]
+        fn fish_round_trips(paths in proptest::collection::vec(path_strategy(), 1..4)) {
+            for separator in [PathSeparator::Newline, PathSeparator::Nul] {
+                let joined = join_paths(&paths, QuoteStyle::Fish, separator);
+                prop_assert_eq!(split_fish(&joined), paths.clone());
+            }
+        }
+
+        #[test]
+        fn powershell_round_trips(paths in proptest::collection::vec(path_strategy(), 1..4)) {
+            let joined = join_paths(&paths, QuoteStyle::PowerShell, PathSeparator::Space);
+            prop_assert_eq!(split_powershell(&joined), paths);
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::path::{self, PathMapping};
+
+/// Windows → WSL 路径解析器，返回 None 表示交给链上的下一个解析器
+pub trait PathResolver: Send + Sync {
+    fn to_wsl(&self, windows_path: &str) -> Option<String>;
+}
+
+/// 解析器类型（`path_resolvers` 配置项）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResolverKind {
+    /// 内置 `/mnt/<盘符>` 规则
+    Builtin,
+    /// `[[path_mapping]]` 映射表
+    Mapping,
+    /// 外部命令（如 `wsl.exe -e wslpath -u {path}`）
+    Command,
+}
+
+/// 内置规则：盘符路径转换为 `/mnt/<盘符>/...`
+pub struct BuiltinResolver;
+
+impl PathResolver for BuiltinResolver {
+    fn to_wsl(&self, windows_path: &str) -> Option<String> {
+        let converted = path::convert_path_to_wsl(windows_path, &[]);
+        (!converted.is_empty()).then_some(converted)
+    }
+}
+
+/// 映射表：只处理命中 `[[path_mapping]]` 前缀的路径
+pub struct MappingResolver {
+    mappings: Vec<PathMapping>,
+}
+
+impl MappingResolver {
+    pub fn new(mappings: Vec<PathMapping>) -> Self {
+        Self { mappings }
+    }
+}
+
+impl PathResolver for MappingResolver {
+    fn to_wsl(&self, windows_path: &str) -> Option<String> {
+        path::map_path_to_wsl(windows_path, &self.mappings)
+    }
+}
+
+/// 外部命令解析器：按模板执行命令，取 stdout 第一行作为结果
+///
+/// 模板按空白拆分参数（双引号可包含空格），`{path}` 替换为 Windows 路径；
+/// 成功结果按路径缓存（最多 `CACHE_CAPACITY` 项，配置重新加载时随解析链重建），
+/// 超时的进程会被终止。命令在 `block_in_place` 中执行，不阻塞异步运行时的其他任务。
+pub struct CommandResolver {
+    template: Vec<String>,
+    timeout: Duration,
+    cache: Mutex<HashMap<String, String>>,
+}
+
+/// 命令解析结果的缓存容量，写满后清空重新缓存
+const CACHE_CAPACITY: usize = 256;
+
+/// 取命令输出的第一行作为解析结果，空行视为失败
+fn parse_output(output: &[u8]) -> Option<String> {
+    let output = String::from_utf8_lossy(output);
+    let line = output.lines().next()?.trim();
+    (!line.is_empty()).then(|| line.to_string())
+}
+
+impl CommandResolver {
+    pub fn new(template: &str, timeout: Duration) -> Self {
+        Self {
+            template: split_template(template),
+            timeout,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn run(&self, windows_path: &str) -> Option<String> {
+        let (program, args) = self.template.split_first()?;
+
+        let mut command = hidden_command(program);
+        command
+            .args(args.iter().map(|arg| arg.replace("{path}", windows_path)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                warn!("路径解析命令启动失败 {}: {}", program, e);
+                return None;
+            }
+        };
+
+        // 在独立线程读取 stdout，避免输出较多时管道写满阻塞子进程
+        let mut stdout = child.stdout.take()?;
+        let reader = std::thread::spawn(move || {
+            let mut output = Vec::new();
+            let _ = stdout.read_to_end(&mut output);
+            output
+        });
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) if status.success() => break,
+                Ok(Some(status)) => {
+                    warn!("路径解析命令失败: {} ({})", windows_path, status);
+                    return None;
+                }
+                Ok(None) if Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Ok(None) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    warn!("路径解析命令超时: {}", windows_path);
+                    return None;
+                }
+                Err(e) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    warn!("无法查询路径解析命令状态 {}: {}", windows_path, e);
+                    return None;
+                }
+            }
+        }
+
+        parse_output(&reader.join().ok()?)
+    }
+}
+
+impl PathResolver for CommandResolver {
+    fn to_wsl(&self, windows_path: &str) -> Option<String> {
+        if let Ok(cache) = self.cache.lock() {
+            if let Some(cached) = cache.get(windows_path) {
+                return Some(cached.clone());
+            }
+        }
+
+        let resolved = tokio::task::block_in_place(|| self.run(windows_path))?;
+
+        if let Ok(mut cache) = self.cache.lock() {
+            if cache.len() >= CACHE_CAPACITY {
+                cache.clear();
+            }
+            cache.insert(windows_path.to_string(), resolved.clone());
+        }
+
+        Some(resolved)
+    }
+}
+
+/// 按顺序尝试多个解析器，第一个成功的结果生效
+pub struct ChainResolver {
+    resolvers: Vec<Box<dyn PathResolver>>,
+}
+
+impl ChainResolver {
+    pub fn new(resolvers: Vec<Box<dyn PathResolver>>) -> Self {
+        Self { resolvers }
+    }
+
+    /// 按配置构建解析链
+    pub fn from_config(
+        kinds: &[ResolverKind],
+        mappings: &[PathMapping],
+        command: Option<&str>,
+        timeout: Duration,
+    ) -> Self {
+        let mut resolvers: Vec<Box<dyn PathResolver>> = Vec::new();
+
+        for kind in kinds {
+            match kind {
+                ResolverKind::Builtin => resolvers.push(Box::new(BuiltinResolver)),
+                ResolverKind::Mapping => {
+                    resolvers.push(Box::new(MappingResolver::new(mappings.to_vec())))
+                }
+                ResolverKind::Command => match command {
+                    Some(template) if !template.trim().is_empty() => {
+                        resolvers.push(Box::new(CommandResolver::new(template, timeout)))
+                    }
+                    _ => warn!("path_resolvers 包含 command，但未配置 resolver_command"),
+                },
+            }
+        }
+
+        Self::new(resolvers)
+    }
+}
+
+impl PathResolver for ChainResolver {
+    fn to_wsl(&self, windows_path: &str) -> Option<String> {
+        self.resolvers
+            .iter()
+            .find_map(|resolver| resolver.to_wsl(windows_path))
+    }
+}
+
+/// 配置的发行版名称，未配置时查询默认 WSL 发行版
+pub fn wsl_distro(configured: Option<&str>) -> Option<String> {
+    configured.map(str::to_string).or_else(default_wsl_distro)
+}
+
+/// 查询默认 WSL 发行版名称（`wsl.exe -l -q` 的第一项），结果在进程内缓存
+fn default_wsl_distro() -> Option<String> {
+    static DISTRO: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+
+    DISTRO
+        .get_or_init(|| {
+            let output = hidden_command("wsl.exe").args(["-l", "-q"]).output().ok()?;
+
+            // wsl.exe 输出 UTF-16LE
+            let units: Vec<u16> = output
+                .stdout
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+                .lines()
+                .map(|line| line.trim_matches(|c: char| c == '\0' || c.is_whitespace()))
+                .find(|line| !line.is_empty())
+                .map(str::to_string)
+        })
+        .clone()
+}
+
+/// 创建不弹出控制台窗口的子进程命令
+fn hidden_command(program: &str) -> Command {
+    #[allow(unused_mut)]
+    let mut command = Command::new(program);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    command
+}
+
+/// 按空白拆分命令模板，双引号内的空白保留
+fn split_template(template: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+
+    for ch in template.chars() {
+        match ch {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            ch if ch.is_whitespace() && !in_quotes => {
+                if has_token {
+                    parts.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            ch => {
+                current.push(ch);
+                has_token = true;
+            }
+        }
+    }
+
+    if has_token {
+        parts.push(current);
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_output, split_template, BuiltinResolver, ChainResolver, MappingResolver, PathResolver,
+    };
+    #[cfg(windows)]
+    use super::CommandResolver;
+    use crate::path::PathMapping;
+    #[cfg(windows)]
+    use std::path::{Path, PathBuf};
+    #[cfg(windows)]
+    use std::time::{Duration, Instant};
+
+    /// 在临时目录写入假的解析脚本，每次调用向 calls.txt 追加一行
+    #[cfg(windows)]
+    fn fake_script(name: &str, body: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!(
+            "wsl_clipboard_resolver_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let calls = dir.join("calls.txt");
+        let script = dir.join("fake_wslpath.cmd");
+        let content = format!(
+            "@echo off\r\necho %~1>>\"{}\"\r\n{}\r\n",
+            calls.display(),
+            body
+        );
+        std::fs::write(&script, content).unwrap();
+        (script, calls)
+    }
+
+    #[cfg(windows)]
+    fn template(script: &Path) -> String {
+        format!("\"{}\" {{path}}", script.display())
+    }
+
+    #[test]
+    fn template_splitting_keeps_quoted_arguments() {
+        assert_eq!(
+            split_template(r#"wsl.exe -e wslpath -u {path}"#),
+            vec!["wsl.exe", "-e", "wslpath", "-u", "{path}"]
+        );
+        assert_eq!(
+            split_template(r#""C:\Program Files\tool.exe"  --x "" {path}"#),
+            vec![r"C:\Program Files\tool.exe", "--x", "", "{path}"]
+        );
+    }
+
+    #[test]
+    fn output_parsing_takes_first_line() {
+        assert_eq!(parse_output(b"  /mnt/c/a b.png \r\n/second\n").as_deref(), Some("/mnt/c/a b.png"));
+        assert_eq!(parse_output(b"\n/second\n"), None);
+        assert_eq!(parse_output(b""), None);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn command_resolver_uses_output_and_cache() {
+        let (script, calls) = fake_script("cache", "echo /fake/resolved");
+        let resolver = CommandResolver::new(&template(&script), Duration::from_secs(10));
+
+        assert_eq!(resolver.to_wsl(r"C:\a b.png").as_deref(), Some("/fake/resolved"));
+        assert_eq!(resolver.to_wsl(r"C:\a b.png").as_deref(), Some("/fake/resolved"));
+
+        let recorded = std::fs::read_to_string(&calls).unwrap();
+        assert_eq!(recorded.lines().collect::<Vec<_>>(), vec![r"C:\a b.png"]);
+
+        let _ = std::fs::remove_dir_all(script.parent().unwrap());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn command_resolver_times_out_and_falls_back() {
+        let (script, _) = fake_script("timeout", "ping -n 6 127.0.0.1 >nul\r\necho /too/late");
+        let command = CommandResolver::new(&template(&script), Duration::from_millis(300));
+        let chain = ChainResolver::new(vec![Box::new(command), Box::new(BuiltinResolver)]);
+
+        let started = Instant::now();
+        assert_eq!(chain.to_wsl(r"C:\x.png").as_deref(), Some("/mnt/c/x.png"));
+        assert!(started.elapsed() < Duration::from_secs(4));
+
+        let _ = std::fs::remove_dir_all(script.parent().unwrap());
+    }
+
+    #[test]
+    fn chain_tries_resolvers_in_order() {
+        let mapping = MappingResolver::new(vec![PathMapping {
+            from: r"\\server\share".to_string(),
+            to: "/mnt/share".to_string(),
+        }]);
+        let chain = ChainResolver::new(vec![Box::new(mapping), Box::new(BuiltinResolver)]);
+
+        assert_eq!(chain.to_wsl(r"\\server\share\a").as_deref(), Some("/mnt/share/a"));
+        assert_eq!(chain.to_wsl(r"C:\a").as_deref(), Some("/mnt/c/a"));
+        assert_eq!(chain.to_wsl(r"\\other\x"), None);
+        assert_eq!(ChainResolver::new(Vec::new()).to_wsl(r"C:\a"), None);
+    }
+}
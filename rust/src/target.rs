@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+use crate::profile::{WindowInfo, WindowRule};
+
+/// 热键生效的窗口范围（`[target]`）
+///
+/// ```toml
+/// [target]
+/// include = [{ process = "WindowsTerminal.exe" }, { title = "(?i)claude" }]
+/// exclude = [{ process = "WINWORD.EXE" }]
+/// fallback_exclude = [{ window_class = "CabinetWClass" }]
+/// ```
+///
+/// - `include` 为空时匹配所有窗口，`exclude` 优先于 `include`；
+///   不在范围内的窗口中按下热键时，热键按原样转发给该窗口（如 Office 的 Alt+V）。
+/// - `fallback_include` / `fallback_exclude` 规则相同，作用于剪贴板没有图片和文件时的普通粘贴：
+///   不在范围内时同样转发原热键，而不是发送粘贴按键。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TargetFilter {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<WindowRule>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<WindowRule>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_include: Vec<WindowRule>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_exclude: Vec<WindowRule>,
+}
+
+impl TargetFilter {
+    /// 是否未配置任何规则
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty()
+            && self.exclude.is_empty()
+            && self.fallback_include.is_empty()
+            && self.fallback_exclude.is_empty()
+    }
+
+    /// 热键是否在该窗口中生效
+    pub fn allows(&self, window: Option<&WindowInfo>) -> bool {
+        rules_allow(&self.include, &self.exclude, window)
+    }
+
+    /// 剪贴板没有可处理内容时，是否在该窗口中执行普通粘贴
+    pub fn allows_fallback(&self, window: Option<&WindowInfo>) -> bool {
+        rules_allow(&self.fallback_include, &self.fallback_exclude, window)
+    }
+}
+
+/// 无法获取前台窗口时视为在范围内，保持原有行为
+fn rules_allow(include: &[WindowRule], exclude: &[WindowRule], window: Option<&WindowInfo>) -> bool {
+    let Some(window) = window else {
+        return true;
+    };
+
+    if exclude.iter().any(|rule| rule.matches(window)) {
+        return false;
+    }
+    include.is_empty() || include.iter().any(|rule| rule.matches(window))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TargetFilter;
+    use crate::profile::WindowInfo;
+
+    fn window(process: &str, class: &str, title: &str) -> WindowInfo {
+        WindowInfo {
+            process_name: process.to_string(),
+            class_name: class.to_string(),
+            title: title.to_string(),
+        }
+    }
+
+    #[test]
+    fn include_and_exclude_rules() {
+        let filter: TargetFilter = toml::from_str(
+            r#"
+            include = [{ process = "WindowsTerminal" }, { title = "(?i)claude" }]
+            exclude = [{ title = "Settings" }]
+            fallback_exclude = [{ window_class = "CabinetWClass" }]
+            "#,
+        )
+        .unwrap();
+
+        let terminal = window("WindowsTerminal.exe", "CASCADIA", "Ubuntu");
+        let browser = window("chrome.exe", "Chrome_WidgetWin_1", "Claude - Chrome");
+        let explorer = window("explorer.exe", "CabinetWClass", "Downloads");
+
+        assert!(filter.allows(Some(&terminal)));
+        assert!(filter.allows(Some(&browser)));
+        assert!(!filter.allows(Some(&explorer)));
+        assert!(!filter.allows(Some(&window("WindowsTerminal.exe", "CASCADIA", "Settings"))));
+        assert!(filter.allows(None));
+
+        assert!(filter.allows_fallback(Some(&terminal)));
+        assert!(!filter.allows_fallback(Some(&explorer)));
+    }
+
+    #[test]
+    fn empty_filter_allows_everything() {
+        let filter = TargetFilter::default();
+        assert!(filter.is_empty());
+        assert!(filter.allows(Some(&window("WINWORD.EXE", "OpusApp", "Doc"))));
+        assert!(filter.allows_fallback(Some(&window("WINWORD.EXE", "OpusApp", "Doc"))));
+    }
+}
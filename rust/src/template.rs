@@ -0,0 +1,147 @@
+/// 输出模板可用的变量
+///
+/// | 占位符 | 含义 |
+/// | --- | --- |
+/// | `{path}` | 按 `path_style` 与 `quote_style` 格式化后的路径 |
+/// | `{wsl_path}` | WSL 路径（不加引号） |
+/// | `{windows_path}` | Windows 路径（不加引号） |
+/// | `{name}` | 文件名 |
+/// | `{width}` / `{height}` | 图片宽高（未知时为空） |
+/// | `{size}` | 文件字节数（未知时为空） |
+/// | `{index}` | 序号，从 1 开始 |
+///
+/// `{{` 与 `}}` 输出字面量花括号，未知占位符原样保留。
+#[derive(Debug, Clone, Default)]
+pub struct TemplateVars<'a> {
+    pub path: &'a str,
+    pub wsl_path: &'a str,
+    pub windows_path: &'a str,
+    pub name: &'a str,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub size: Option<u64>,
+    pub index: usize,
+}
+
+impl TemplateVars<'_> {
+    fn lookup(&self, key: &str) -> Option<String> {
+        let optional = |value: Option<u64>| value.map(|v| v.to_string()).unwrap_or_default();
+
+        let value = match key {
+            "path" => self.path.to_string(),
+            "wsl_path" => self.wsl_path.to_string(),
+            "windows_path" => self.windows_path.to_string(),
+            "name" => self.name.to_string(),
+            "width" => optional(self.width.map(u64::from)),
+            "height" => optional(self.height.map(u64::from)),
+            "size" => optional(self.size),
+            "index" => self.index.to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// 按模板渲染粘贴文本，如 `@{path} `、`![{name}]({wsl_path})`
+pub fn render(template: &str, vars: &TemplateVars) -> String {
+    let mut output = String::with_capacity(template.len() + vars.path.len());
+    let mut rest = template;
+
+    while let Some(pos) = rest.find(['{', '}']) {
+        output.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            output.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+
+        let placeholder = tail.strip_prefix('{').and_then(|inner| {
+            let key = &inner[..inner.find('}')?];
+            Some((key.len(), vars.lookup(key)?))
+        });
+
+        if let Some((key_len, value)) = placeholder {
+            output.push_str(&value);
+            rest = &tail[key_len + 2..];
+            continue;
+        }
+
+        output.push_str(&tail[..1]);
+        rest = &tail[1..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// 从 PNG 文件头（IHDR）读取宽高
+pub fn png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    if data.len() < 24 || !data.starts_with(SIGNATURE) || &data[12..16] != b"IHDR" {
+        return None;
+    }
+
+    let width = u32::from_be_bytes(data[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(data[20..24].try_into().ok()?);
+    Some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{png_dimensions, render, TemplateVars};
+
+    fn vars() -> TemplateVars<'static> {
+        TemplateVars {
+            path: "'/mnt/c/a b.png'",
+            wsl_path: "/mnt/c/a b.png",
+            windows_path: r"C:\a b.png",
+            name: "a b.png",
+            width: Some(640),
+            height: Some(480),
+            size: Some(1024),
+            index: 2,
+        }
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        let cases = [
+            ("{path}", "'/mnt/c/a b.png'"),
+            ("@{path} ", "@'/mnt/c/a b.png' "),
+            ("![{name}]({wsl_path})", "![a b.png](/mnt/c/a b.png)"),
+            ("{index}. {windows_path} {width}x{height} {size}B", r"2. C:\a b.png 640x480 1024B"),
+            ("{{path}} {unknown} {", "{path} {unknown} {"),
+            ("}}{path", "}{path"),
+            ("", ""),
+        ];
+
+        for (template, expected) in cases {
+            assert_eq!(render(template, &vars()), expected, "template: {template}");
+        }
+    }
+
+    #[test]
+    fn unknown_values_render_empty() {
+        let file = TemplateVars {
+            width: None,
+            height: None,
+            size: None,
+            ..vars()
+        };
+        assert_eq!(render("[{width}x{height}|{size}]", &file), "[x|]");
+    }
+
+    #[test]
+    fn reads_png_dimensions() {
+        let mut header = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        header.extend_from_slice(&640u32.to_be_bytes());
+        header.extend_from_slice(&480u32.to_be_bytes());
+
+        assert_eq!(png_dimensions(&header), Some((640, 480)));
+        assert_eq!(png_dimensions(&header[..20]), None);
+        assert_eq!(png_dimensions(b"GIF89a"), None);
+    }
+}
@@ -1,46 +1,70 @@
 use crate::cleanup;
-use crate::config::{AppConfig, RuntimeMode};
-use crate::hotkey::{HotkeyManager, HotkeyType};
+use crate::config::{AppConfig, PathStyle, RuntimeMode};
+use crate::hotkey::{self, HotkeyConflict, HotkeyManager};
 use anyhow::{Context, Result};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicIsize, Ordering};
 use std::sync::mpsc as std_mpsc;
+use std::sync::{Mutex, PoisonError};
 use tracing::{error, info, warn};
 use windows::core::PCWSTR;
 use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
 use windows::Win32::UI::Shell::{
-    Shell_NotifyIconW, NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NIM_MODIFY,
-    NOTIFYICONDATAW,
+    Shell_NotifyIconW, NIF_ICON, NIF_INFO, NIF_MESSAGE, NIF_TIP, NIIF_WARNING, NIM_ADD,
+    NIM_DELETE, NIM_MODIFY, NOTIFYICONDATAW,
 };
 use windows::Win32::UI::WindowsAndMessaging::*;
 
 /// 托盘图标回调消息
 const WM_TRAYICON: u32 = WM_APP + 100;
+/// 主循环有待处理的请求（见 [`request`]）
+const WM_TRAY_REQUEST: u32 = WM_APP + 101;
 const APP_ICON_ID: u16 = 1;
 
 /// 菜单命令 ID
-const CMD_HOTKEY_ALTV: u32 = 1001;
-const CMD_HOTKEY_CTRLALTV: u32 = 1002;
-const CMD_HOTKEY_ALTENTER: u32 = 1003;
+const CMD_HOTKEY_BASE: u32 = 1001;
 const CMD_MODE_SAFE: u32 = 2001;
 const CMD_MODE_FAST: u32 = 2002;
 const CMD_OPEN_FOLDER: u32 = 3001;
+const CMD_PATH_STYLE_BASE: u32 = 5001;
 const CMD_EXIT: u32 = 4001;
 
 /// 托盘发往主循环的命令
 #[derive(Debug, Clone)]
 pub enum TrayCommand {
-    SwitchHotkey(HotkeyType),
+    SwitchHotkey(String),
     SwitchMode(RuntimeMode),
+    SwitchPathStyle(PathStyle),
     OpenFolder,
     Exit,
 }
 
+/// 主循环发往托盘线程的请求
+///
+/// 热键注册在托盘线程上，注册、注销与转发都需要在该线程执行。
+pub enum TrayRequest {
+    /// 将热键按原样转发给前台窗口
+    PassthroughHotkey(u32),
+    /// 应用热重载后的配置
+    ApplyConfig(Box<AppConfig>),
+    /// 显示气泡通知
+    Notify { title: String, text: String },
+}
+
+/// 待托盘线程处理的请求
+static PENDING_REQUESTS: Mutex<Vec<TrayRequest>> = Mutex::new(Vec::new());
+
 /// 线程局部存储：用于在 wnd_proc 中访问状态
 struct TrayState {
     nid: NOTIFYICONDATAW,
     config: AppConfig,
     hotkey_manager: HotkeyManager,
+    /// 正常状态与热键未绑定状态的托盘图标
+    app_icon: HICON,
+    unbound_icon: HICON,
+    /// 热键子菜单中的热键（配置字符串）
+    hotkey_menu: Vec<String>,
     cmd_tx: std_mpsc::Sender<TrayCommand>,
     temp_dir: PathBuf,
     session_end_cleanup_done: bool,
@@ -49,6 +73,9 @@ struct TrayState {
 // 全局状态指针（仅托盘线程访问）
 static mut TRAY_STATE: *mut TrayState = std::ptr::null_mut();
 
+// 托盘隐藏窗口句柄，供其他线程投递消息
+static TRAY_HWND: AtomicIsize = AtomicIsize::new(0);
+
 /// 托盘控制器
 pub struct TrayController;
 
@@ -133,34 +160,42 @@ fn run_tray_thread(
         nid.uID = 1;
         nid.uFlags = NIF_ICON | NIF_MESSAGE | NIF_TIP;
         nid.uCallbackMessage = WM_TRAYICON;
-        nid.hIcon = load_app_icon(h_instance.into())?;
 
-        // 设置 tooltip
-        set_tooltip(&mut nid, &config);
-
-        Shell_NotifyIconW(NIM_ADD, &nid);
+        let app_icon = load_app_icon(h_instance.into())?;
+        let unbound_icon = LoadIconW(HINSTANCE::default(), IDI_WARNING).unwrap_or(app_icon);
 
         // 在托盘线程上创建热键管理器
         let mut hotkey_manager = HotkeyManager::new()?;
 
-        // 注册初始热键
-        let initial_hotkey = HotkeyType::from_config(&config.hotkey)
-            .unwrap_or(HotkeyType::AltV);
-        if let Err(e) = hotkey_manager.register(initial_hotkey) {
-            warn!("注册初始热键失败: {}", e);
-        }
+        register_primary_hotkey(&mut hotkey_manager, &config);
+
+        // 设置图标与 tooltip
+        let bound = hotkey_manager.current_hotkey().is_some();
+        nid.hIcon = if bound { app_icon } else { unbound_icon };
+        set_tooltip(&mut nid, &config, hotkey_manager.current_combo());
+
+        Shell_NotifyIconW(NIM_ADD, &nid);
+
+        announce_primary_hotkey(&nid, &cmd_tx, &config, hotkey_manager.current_combo(), true);
+        register_secondary_hotkeys(&mut hotkey_manager, &config);
+
+        let hotkey_menu = hotkey_menu_entries(&config);
 
         // 创建状态
         let mut state = Box::new(TrayState {
             nid,
             config,
             hotkey_manager,
+            app_icon,
+            unbound_icon,
+            hotkey_menu,
             cmd_tx,
             temp_dir,
             session_end_cleanup_done: false,
         });
 
         TRAY_STATE = &mut *state as *mut TrayState;
+        TRAY_HWND.store(hwnd.0, Ordering::Release);
 
         // 消息循环
         let mut msg = MSG::default();
@@ -181,6 +216,7 @@ fn run_tray_thread(
         let _ = DestroyWindow(hwnd);
         let _ = UnregisterClassW(class_name, h_instance);
         TRAY_STATE = std::ptr::null_mut();
+        TRAY_HWND.store(0, Ordering::Release);
 
         info!("托盘线程已退出");
     }
@@ -188,11 +224,119 @@ fn run_tray_thread(
     Ok(())
 }
 
+/// 注册主热键：配置的热键被占用或无效时依次尝试后备热键
+fn register_primary_hotkey(hotkey_manager: &mut HotkeyManager, config: &AppConfig) {
+    let candidates: Vec<&str> = std::iter::once(config.hotkey.as_str())
+        .chain(config.hotkey_fallbacks.iter().map(String::as_str))
+        .collect();
+    hotkey_manager.register_with_fallback(&candidates);
+}
+
+/// 注册反向转换热键与动作表热键
+fn register_secondary_hotkeys(hotkey_manager: &mut HotkeyManager, config: &AppConfig) {
+    match &config.reverse_hotkey {
+        Some(reverse_hotkey) => {
+            if let Err(e) = hotkey_manager.register_reverse(reverse_hotkey) {
+                warn!("注册反向转换热键失败: {}", e);
+            }
+        }
+        None => {
+            if let Err(e) = hotkey_manager.unregister_reverse() {
+                warn!("注销反向转换热键失败: {}", e);
+            }
+        }
+    }
+
+    let combos: Vec<&str> = config.actions.iter().map(|a| a.hotkey.as_str()).collect();
+    if let Err(e) = hotkey_manager.register_actions(&combos) {
+        error!("{:#}", e);
+    }
+}
+
+/// 主热键不是配置的组合时通知主循环实际绑定的热键，`notify` 为 true 时同时提示用户
+unsafe fn announce_primary_hotkey(
+    nid: &NOTIFYICONDATAW,
+    cmd_tx: &std_mpsc::Sender<TrayCommand>,
+    config: &AppConfig,
+    current_combo: &str,
+    notify: bool,
+) {
+    if current_combo == config.hotkey {
+        return;
+    }
+    if !current_combo.is_empty() {
+        let _ = cmd_tx.send(TrayCommand::SwitchHotkey(current_combo.to_string()));
+    }
+    if !notify {
+        return;
+    }
+
+    let configured = hotkey::display_name(&config.hotkey);
+    if current_combo.is_empty() {
+        show_balloon(
+            nid,
+            "热键未绑定",
+            &format!("{} 不可用且后备热键均注册失败，请右键托盘图标在“快捷键”中选择其他组合", configured),
+        );
+        return;
+    }
+
+    show_balloon(
+        nid,
+        "热键已改用后备组合",
+        &format!("{} 不可用，已改用 {}", configured, hotkey::display_name(current_combo)),
+    );
+}
+
+/// 应用热重载后的配置：重新注册有变化的热键，更新菜单与图标
+unsafe fn apply_config(state: &mut TrayState, config: AppConfig) {
+    let primary_changed = config.hotkey != state.config.hotkey
+        || config.hotkey_fallbacks != state.config.hotkey_fallbacks
+        || state.hotkey_manager.current_hotkey().is_none();
+    if primary_changed {
+        register_primary_hotkey(&mut state.hotkey_manager, &config);
+    }
+    let current_combo = state.hotkey_manager.current_combo();
+    announce_primary_hotkey(&state.nid, &state.cmd_tx, &config, current_combo, primary_changed);
+    register_secondary_hotkeys(&mut state.hotkey_manager, &config);
+
+    state.hotkey_menu = hotkey_menu_entries(&config);
+    state.config = config;
+    refresh_tray_icon(state);
+    info!("托盘已应用新配置");
+}
+
+/// 托盘热键菜单项：配置的预设热键，之后追加不在其中的当前热键与后备热键；重复与无效的项跳过
+fn hotkey_menu_entries(config: &AppConfig) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut entries = Vec::new();
+
+    let combos = config
+        .hotkey_presets
+        .iter()
+        .chain([&config.hotkey])
+        .chain(&config.hotkey_fallbacks);
+    for combo in combos {
+        match hotkey::hotkey_id(combo) {
+            Ok(id) if !ids.contains(&id) => {
+                ids.push(id);
+                entries.push(combo.clone());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("忽略无效的热键预设: {}", e),
+        }
+    }
+
+    entries
+}
+
 /// 设置 tooltip 文本
-fn set_tooltip(nid: &mut NOTIFYICONDATAW, config: &AppConfig) {
-    let hotkey_display = HotkeyType::from_config(&config.hotkey)
-        .map(|h| h.display_name())
-        .unwrap_or("Alt+V");
+fn set_tooltip(nid: &mut NOTIFYICONDATAW, config: &AppConfig, hotkey_combo: &str) {
+    let hotkey_display = if hotkey_combo.is_empty() {
+        "热键未绑定".to_string()
+    } else {
+        hotkey::display_name(hotkey_combo)
+    };
 
     let mode_display = match &config.runtime_mode {
         RuntimeMode::Safe => "兼容",
@@ -200,10 +344,34 @@ fn set_tooltip(nid: &mut NOTIFYICONDATAW, config: &AppConfig) {
     };
 
     let tip = format!("WSL Clipboard ({} | {})", hotkey_display, mode_display);
-    let tip_utf16: Vec<u16> = tip.encode_utf16().collect();
-    let len = tip_utf16.len().min(nid.szTip.len() - 1);
-    nid.szTip[..len].copy_from_slice(&tip_utf16[..len]);
-    nid.szTip[len] = 0;
+    copy_wide(&mut nid.szTip, &tip);
+}
+
+/// 将文本写入定长 UTF-16 缓冲区，超长时截断并保留结尾的 0
+fn copy_wide(buffer: &mut [u16], text: &str) {
+    let text_utf16: Vec<u16> = text.encode_utf16().collect();
+    let len = text_utf16.len().min(buffer.len() - 1);
+    buffer[..len].copy_from_slice(&text_utf16[..len]);
+    buffer[len] = 0;
+}
+
+/// 显示托盘气泡通知
+unsafe fn show_balloon(nid: &NOTIFYICONDATAW, title: &str, text: &str) {
+    let mut balloon = *nid;
+    balloon.uFlags = NIF_INFO;
+    balloon.dwInfoFlags = NIIF_WARNING;
+    copy_wide(&mut balloon.szInfoTitle, title);
+    copy_wide(&mut balloon.szInfo, text);
+    Shell_NotifyIconW(NIM_MODIFY, &balloon);
+}
+
+/// 按主热键是否已绑定更新托盘图标与 tooltip
+unsafe fn refresh_tray_icon(state: &mut TrayState) {
+    let bound = state.hotkey_manager.current_hotkey().is_some();
+    state.nid.hIcon = if bound { state.app_icon } else { state.unbound_icon };
+    set_tooltip(&mut state.nid, &state.config, state.hotkey_manager.current_combo());
+    state.nid.uFlags = NIF_ICON | NIF_TIP;
+    Shell_NotifyIconW(NIM_MODIFY, &state.nid);
 }
 
 /// 窗口过程
@@ -221,6 +389,11 @@ unsafe extern "system" fn tray_wnd_proc(
         return LRESULT(0);
     }
 
+    if msg == WM_TRAY_REQUEST {
+        handle_requests();
+        return LRESULT(0);
+    }
+
     if msg == WM_COMMAND {
         let cmd_id = (wparam.0 & 0xFFFF) as u32;
         handle_menu_command(cmd_id);
@@ -274,23 +447,30 @@ unsafe fn show_context_menu(hwnd: HWND) {
         Err(_) => return,
     };
 
+    // ---- 热键未绑定提示 ----
+    if state.hotkey_manager.current_hotkey().is_none() {
+        let warning_label: Vec<u16> = "热键未绑定，请在“快捷键”中选择其他组合\0".encode_utf16().collect();
+        let _ = AppendMenuW(h_menu, MF_STRING | MF_GRAYED, 0, PCWSTR::from_raw(warning_label.as_ptr()));
+        let _ = AppendMenuW(h_menu, MF_SEPARATOR, 0, PCWSTR::null());
+    }
+
     // ---- 热键子菜单 ----
     let h_hotkey_menu = match CreatePopupMenu() {
         Ok(m) => m,
         Err(_) => { let _ = DestroyMenu(h_menu); return; }
     };
 
-    let current_hotkey = state.hotkey_manager.current_type();
-    for ht in HotkeyType::all() {
-        let label = format!("{}\0", ht.display_name());
-        let label_w: Vec<u16> = label.encode_utf16().collect();
-        let cmd_id = match ht {
-            HotkeyType::AltV => CMD_HOTKEY_ALTV,
-            HotkeyType::CtrlAltV => CMD_HOTKEY_CTRLALTV,
-            HotkeyType::AltEnter => CMD_HOTKEY_ALTENTER,
+    let current_id = state.hotkey_manager.current_hotkey().map(|h| h.id());
+    for (index, combo) in state.hotkey_menu.iter().enumerate() {
+        let label = if state.hotkey_manager.is_conflicted(combo) {
+            format!("{}（已被占用）\0", hotkey::display_name(combo))
+        } else {
+            format!("{}\0", hotkey::display_name(combo))
         };
+        let label_w: Vec<u16> = label.encode_utf16().collect();
+        let cmd_id = CMD_HOTKEY_BASE + index as u32;
         let mut flags = MF_STRING;
-        if *ht == current_hotkey {
+        if current_id.is_some() && hotkey::hotkey_id(combo).ok() == current_id {
             flags |= MF_CHECKED;
         }
         let _ = AppendMenuW(h_hotkey_menu, flags, cmd_id as usize, PCWSTR::from_raw(label_w.as_ptr()));
@@ -318,6 +498,26 @@ unsafe fn show_context_menu(hwnd: HWND) {
     let mode_label: Vec<u16> = "运行模式\0".encode_utf16().collect();
     let _ = AppendMenuW(h_menu, MF_POPUP, h_mode_menu.0 as usize, PCWSTR::from_raw(mode_label.as_ptr()));
 
+    // ---- 路径格式子菜单 ----
+    let h_style_menu = match CreatePopupMenu() {
+        Ok(m) => m,
+        Err(_) => { let _ = DestroyMenu(h_menu); return; }
+    };
+
+    for (index, style) in PathStyle::all().iter().enumerate() {
+        let label = format!("{}\0", style.display_name());
+        let label_w: Vec<u16> = label.encode_utf16().collect();
+        let mut flags = MF_STRING;
+        if *style == state.config.path_style {
+            flags |= MF_CHECKED;
+        }
+        let cmd_id = CMD_PATH_STYLE_BASE + index as u32;
+        let _ = AppendMenuW(h_style_menu, flags, cmd_id as usize, PCWSTR::from_raw(label_w.as_ptr()));
+    }
+
+    let style_label: Vec<u16> = "路径格式\0".encode_utf16().collect();
+    let _ = AppendMenuW(h_menu, MF_POPUP, h_style_menu.0 as usize, PCWSTR::from_raw(style_label.as_ptr()));
+
     // ---- 分隔线 ----
     let _ = AppendMenuW(h_menu, MF_SEPARATOR, 0, PCWSTR::null());
 
@@ -350,14 +550,21 @@ unsafe fn handle_menu_command(cmd_id: u32) {
     let state = &mut *TRAY_STATE;
 
     match cmd_id {
-        CMD_HOTKEY_ALTV => switch_hotkey(state, HotkeyType::AltV),
-        CMD_HOTKEY_CTRLALTV => switch_hotkey(state, HotkeyType::CtrlAltV),
-        CMD_HOTKEY_ALTENTER => switch_hotkey(state, HotkeyType::AltEnter),
+        id if (CMD_HOTKEY_BASE..CMD_HOTKEY_BASE + state.hotkey_menu.len() as u32).contains(&id) => {
+            let combo = state.hotkey_menu[(id - CMD_HOTKEY_BASE) as usize].clone();
+            switch_hotkey(state, &combo);
+        }
         CMD_MODE_SAFE => switch_mode(state, RuntimeMode::Safe),
         CMD_MODE_FAST => switch_mode(state, RuntimeMode::Fast),
         CMD_OPEN_FOLDER => {
             let _ = state.cmd_tx.send(TrayCommand::OpenFolder);
         }
+        id if (CMD_PATH_STYLE_BASE..CMD_PATH_STYLE_BASE + PathStyle::all().len() as u32)
+            .contains(&id) =>
+        {
+            let style = PathStyle::all()[(id - CMD_PATH_STYLE_BASE) as usize];
+            switch_path_style(state, style);
+        }
         CMD_EXIT => {
             let _ = state.cmd_tx.send(TrayCommand::Exit);
             PostQuitMessage(0);
@@ -366,27 +573,37 @@ unsafe fn handle_menu_command(cmd_id: u32) {
     }
 }
 
+/// 保存托盘中修改的配置，失败时气泡提示
+unsafe fn save_config(state: &TrayState, previous: &AppConfig) {
+    if let Err(e) = state.config.save(previous) {
+        error!("保存配置失败: {:#}", e);
+        show_balloon(&state.nid, "保存配置失败", &format!("{:#}", e));
+    }
+}
+
 /// 切换热键
-unsafe fn switch_hotkey(state: &mut TrayState, hotkey_type: HotkeyType) {
-    if state.hotkey_manager.current_type() == hotkey_type {
+unsafe fn switch_hotkey(state: &mut TrayState, combo: &str) {
+    if state.hotkey_manager.current_combo() == combo {
         return;
     }
 
-    if let Err(e) = state.hotkey_manager.register(hotkey_type) {
+    if let Err(e) = state.hotkey_manager.register(combo) {
         error!("切换热键失败: {}", e);
+        if e.is::<HotkeyConflict>() {
+            show_balloon(&state.nid, "切换热键失败", &format!("{}，请选择其他组合", e));
+        }
         return;
     }
 
-    state.config.hotkey = hotkey_type.as_str().to_string();
-    let _ = state.config.save();
+    let previous = state.config.clone();
+    state.config.hotkey = combo.to_string();
+    save_config(state, &previous);
 
-    // 更新 tooltip
-    set_tooltip(&mut state.nid, &state.config);
-    state.nid.uFlags = NIF_TIP;
-    Shell_NotifyIconW(NIM_MODIFY, &state.nid);
+    // 更新图标与 tooltip
+    refresh_tray_icon(state);
 
-    let _ = state.cmd_tx.send(TrayCommand::SwitchHotkey(hotkey_type));
-    info!("已切换热键: {}", hotkey_type.display_name());
+    let _ = state.cmd_tx.send(TrayCommand::SwitchHotkey(combo.to_string()));
+    info!("已切换热键: {}", hotkey::display_name(combo));
 }
 
 /// 切换模式
@@ -404,11 +621,12 @@ unsafe fn switch_mode(state: &mut TrayState, mode: RuntimeMode) {
         return;
     }
 
+    let previous = state.config.clone();
     state.config.runtime_mode = mode.clone();
-    let _ = state.config.save();
+    save_config(state, &previous);
 
     // 更新 tooltip
-    set_tooltip(&mut state.nid, &state.config);
+    set_tooltip(&mut state.nid, &state.config, state.hotkey_manager.current_combo());
     state.nid.uFlags = NIF_TIP;
     Shell_NotifyIconW(NIM_MODIFY, &state.nid);
 
@@ -416,6 +634,61 @@ unsafe fn switch_mode(state: &mut TrayState, mode: RuntimeMode) {
     info!("已切换模式: {}", mode_str);
 }
 
+/// 切换路径格式
+unsafe fn switch_path_style(state: &mut TrayState, style: PathStyle) {
+    if state.config.path_style == style {
+        return;
+    }
+
+    let previous = state.config.clone();
+    state.config.path_style = style;
+    save_config(state, &previous);
+
+    let _ = state.cmd_tx.send(TrayCommand::SwitchPathStyle(style));
+    info!("已切换路径格式: {:?}", style);
+}
+
+/// 向托盘线程发送请求
+pub fn request(request: TrayRequest) {
+    let hwnd = TRAY_HWND.load(Ordering::Acquire);
+    if hwnd == 0 {
+        warn!("托盘窗口未就绪，忽略请求");
+        return;
+    }
+
+    PENDING_REQUESTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(request);
+
+    unsafe {
+        if let Err(e) = PostMessageW(HWND(hwnd), WM_TRAY_REQUEST, WPARAM(0), LPARAM(0)) {
+            warn!("投递托盘请求失败: {}", e);
+        }
+    }
+}
+
+/// 处理主循环发来的请求
+unsafe fn handle_requests() {
+    if TRAY_STATE.is_null() {
+        return;
+    }
+    let state = &mut *TRAY_STATE;
+
+    let requests = std::mem::take(&mut *PENDING_REQUESTS.lock().unwrap_or_else(PoisonError::into_inner));
+    for request in requests {
+        match request {
+            TrayRequest::PassthroughHotkey(hotkey_id) => {
+                if let Err(e) = state.hotkey_manager.passthrough(hotkey_id) {
+                    warn!("转发热键失败: {:#}", e);
+                }
+            }
+            TrayRequest::ApplyConfig(config) => apply_config(state, *config),
+            TrayRequest::Notify { title, text } => show_balloon(&state.nid, &title, &text),
+        }
+    }
+}
+
 /// 打开临时文件夹
 pub fn open_temp_folder() -> Result<()> {
     let temp_dir = cleanup::temp_dir_from_current_exe()?;
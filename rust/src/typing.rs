@@ -0,0 +1,129 @@
+/// 模拟键入的单个按键事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEvent {
+    /// KEYEVENTF_UNICODE 事件，携带一个 UTF-16 代码单元
+    Unicode { unit: u16, key_up: bool },
+    /// 虚拟键事件（Tab）
+    VirtualKey { vk: u16, key_up: bool },
+}
+
+const VK_TAB: u16 = 0x09;
+
+/// 将文本转换为按字符分组的按键事件序列
+///
+/// - 换行（`\n`、`\r\n`、`\r`）键入为空格：键入回车会让终端立即执行命令；
+/// - `\t` 输出 Tab 键；
+/// - 其余控制字符（如 NUL）无法键入，直接跳过；
+/// - BMP 之外的字符以代理对输出：先依次按下高、低代理，再依次抬起，
+///   同一字符的事件始终位于同一组，分块时不会被拆开。
+pub fn key_events(text: &str) -> Vec<Vec<KeyEvent>> {
+    let mut groups = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        let group = match ch {
+            '\r' | '\n' => {
+                if ch == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                unicode_char(' ')
+            }
+            '\t' => virtual_key(VK_TAB),
+            ch if ch.is_control() => continue,
+            ch => unicode_char(ch),
+        };
+        groups.push(group);
+    }
+
+    groups
+}
+
+/// 将按字符分组的事件合并为批次，每批最多 `chars_per_chunk` 个字符
+///
+/// 每批对应一次 SendInput 调用；`chars_per_chunk` 为 1 时即逐字符发送。
+pub fn chunk_events(groups: &[Vec<KeyEvent>], chars_per_chunk: usize) -> Vec<Vec<KeyEvent>> {
+    groups
+        .chunks(chars_per_chunk.max(1))
+        .map(|chunk| chunk.concat())
+        .collect()
+}
+
+fn unicode_char(ch: char) -> Vec<KeyEvent> {
+    let mut units = [0u16; 2];
+    let units = ch.encode_utf16(&mut units);
+    let downs = units.iter().map(|&unit| KeyEvent::Unicode { unit, key_up: false });
+    let ups = units.iter().map(|&unit| KeyEvent::Unicode { unit, key_up: true });
+    downs.chain(ups).collect()
+}
+
+fn virtual_key(vk: u16) -> Vec<KeyEvent> {
+    vec![
+        KeyEvent::VirtualKey { vk, key_up: false },
+        KeyEvent::VirtualKey { vk, key_up: true },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chunk_events, key_events, KeyEvent};
+
+    fn unicode(unit: u16, key_up: bool) -> KeyEvent {
+        KeyEvent::Unicode { unit, key_up }
+    }
+
+    #[test]
+    fn plain_characters_are_down_then_up() {
+        assert_eq!(
+            key_events("a图"),
+            vec![
+                vec![unicode(0x61, false), unicode(0x61, true)],
+                vec![unicode(0x56FE, false), unicode(0x56FE, true)],
+            ]
+        );
+    }
+
+    #[test]
+    fn surrogate_pairs_stay_together() {
+        // U+1F600 = D83D DE00
+        let groups = key_events("😀");
+        assert_eq!(
+            groups,
+            vec![vec![
+                unicode(0xD83D, false),
+                unicode(0xDE00, false),
+                unicode(0xD83D, true),
+                unicode(0xDE00, true),
+            ]]
+        );
+
+        let chunks = chunk_events(&key_events("a😀b😀"), 1);
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks[1], groups[0]);
+        assert_eq!(chunks[3], groups[0]);
+    }
+
+    #[test]
+    fn newlines_never_press_enter() {
+        let space = vec![unicode(0x20, false), unicode(0x20, true)];
+        let groups = key_events("a\r\nb\n\0\t");
+        assert_eq!(groups.len(), 5);
+        assert_eq!(groups[1], space);
+        assert_eq!(groups[3], space);
+        assert_eq!(groups[4][0], KeyEvent::VirtualKey { vk: 0x09, key_up: false });
+        assert!(!groups
+            .concat()
+            .iter()
+            .any(|event| matches!(event, KeyEvent::VirtualKey { vk: 0x0D, .. })));
+    }
+
+    #[test]
+    fn chunks_respect_character_boundaries() {
+        let groups = key_events("/mnt/c/😀.png");
+        let chunks = chunk_events(&groups, 4);
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.concat(), groups.concat());
+        assert_eq!(chunk_events(&groups, 0).len(), groups.len());
+        assert!(chunk_events(&[], 8).is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+use windows::core::PWSTR;
+use windows::Win32::Foundation::{CloseHandle, HWND};
+use windows::Win32::System::Threading::{
+    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+    PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetClassNameW, GetForegroundWindow, GetWindowTextLengthW, GetWindowTextW,
+    GetWindowThreadProcessId,
+};
+
+use crate::profile::WindowInfo;
+
+/// 获取前台窗口的进程名、类名与标题
+pub fn foreground_window_info() -> Option<WindowInfo> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0 == 0 {
+            return None;
+        }
+
+        Some(WindowInfo {
+            process_name: window_process_name(hwnd).unwrap_or_default(),
+            class_name: window_class_name(hwnd).unwrap_or_default(),
+            title: window_title(hwnd).unwrap_or_default(),
+        })
+    }
+}
+
+unsafe fn window_class_name(hwnd: HWND) -> Option<String> {
+    let mut buffer = [0u16; 256];
+    let len = GetClassNameW(hwnd, &mut buffer);
+    (len > 0).then(|| String::from_utf16_lossy(&buffer[..len as usize]))
+}
+
+unsafe fn window_process_name(hwnd: HWND) -> Option<String> {
+    window_process(hwnd).1
+}
+
+/// 获取窗口所属进程的 pid 与可执行文件名
+pub fn window_process(hwnd: HWND) -> (u32, Option<String>) {
+    let mut process_id = 0u32;
+    unsafe {
+        GetWindowThreadProcessId(hwnd, Some(&mut process_id));
+    }
+    if process_id == 0 {
+        return (0, None);
+    }
+
+    (process_id, process_image_name(process_id))
+}
+
+fn process_image_name(process_id: u32) -> Option<String> {
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id).ok()?;
+        let mut buffer = vec![0u16; 1024];
+        let mut len = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR::from_raw(buffer.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(process);
+        result.ok()?;
+
+        let path = String::from_utf16_lossy(&buffer[..len as usize]);
+        path.rsplit(['\\', '/']).next().map(str::to_string)
+    }
+}
+
+/// 获取前台窗口标题
+pub fn foreground_window_title() -> Option<String> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0 == 0 {
+            return None;
+        }
+
+        window_title(hwnd)
+    }
+}
+
+unsafe fn window_title(hwnd: HWND) -> Option<String> {
+    let len = GetWindowTextLengthW(hwnd);
+    if len <= 0 {
+        return None;
+    }
+
+    let mut buffer = vec![0u16; len as usize + 1];
+    let copied = GetWindowTextW(hwnd, &mut buffer);
+    if copied <= 0 {
+        return None;
+    }
+
+    Some(String::from_utf16_lossy(&buffer[..copied as usize]))
+}